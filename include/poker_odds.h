@@ -0,0 +1,165 @@
+#ifndef POKER_ODDS_H
+#define POKER_ODDS_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum PoHandCategory {
+  PO_HAND_CATEGORY_HIGH_CARD = 0,
+  PO_HAND_CATEGORY_PAIR = 1,
+  PO_HAND_CATEGORY_TWO_PAIR = 2,
+  PO_HAND_CATEGORY_THREE_OF_A_KIND = 3,
+  PO_HAND_CATEGORY_STRAIGHT = 4,
+  PO_HAND_CATEGORY_FLUSH = 5,
+  PO_HAND_CATEGORY_FULL_HOUSE = 6,
+  PO_HAND_CATEGORY_FOUR_OF_A_KIND = 7,
+  PO_HAND_CATEGORY_STRAIGHT_FLUSH = 8,
+  PO_HAND_CATEGORY_ROYAL_FLUSH = 9,
+  /**
+   * Only possible with wild cards
+   */
+  PO_HAND_CATEGORY_FIVE_OF_A_KIND = 10,
+} PoHandCategory;
+
+/**
+ * Result of every fallible call
+ */
+typedef enum PoStatus {
+  PO_STATUS_OK = 0,
+  /**
+   * A required pointer argument was null
+   */
+  PO_STATUS_NULL_POINTER = 1,
+  /**
+   * A string was not valid UTF-8
+   */
+  PO_STATUS_INVALID_STRING = 2,
+  /**
+   * A card could not be parsed
+   */
+  PO_STATUS_INVALID_CARD = 3,
+  /**
+   * The cards do not make a valid hand or game, such as a repeated card
+   */
+  PO_STATUS_INVALID_GAME = 4,
+  /**
+   * A range could not be parsed
+   */
+  PO_STATUS_INVALID_RANGE = 5,
+  /**
+   * Equity could not be calculated, such as when the range is entirely blocked
+   */
+  PO_STATUS_INVALID_EQUITY = 6,
+} PoStatus;
+
+/**
+ * Opaque handle to a game
+ */
+typedef struct PoGame PoGame;
+
+/**
+ * Opaque handle to a range
+ */
+typedef struct PoRange PoRange;
+
+typedef struct PoHandRank {
+  enum PoHandCategory category;
+  /**
+   * Kicker-correct strength; a higher value is a better hand
+   */
+  uint32_t rank;
+} PoHandRank;
+
+typedef struct PoEquity {
+  double win;
+  double tie;
+  double equity;
+} PoEquity;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Message describing the last error on this thread. The string is owned by the library and is
+ * valid until the next failing call on the same thread.
+ */
+const char *po_last_error(void);
+
+/**
+ * Ranks the best five-card hand among five to seven cards.
+ *
+ * # Safety
+ *
+ * `cards` must be a null-terminated string and `out` must point to writable memory.
+ */
+enum PoStatus po_evaluate_hand(const char *cards, struct PoHandRank *out);
+
+/**
+ * Creates a game from two hole cards and a board of three to five cards. On success `*out`
+ * holds a handle to release with `po_game_free`.
+ *
+ * # Safety
+ *
+ * `hole` and `board` must be null-terminated strings and `out` must point to writable memory.
+ */
+enum PoStatus po_game_new(const char *hole, const char *board, struct PoGame **out);
+
+/**
+ * Releases a game. Passing null does nothing.
+ *
+ * # Safety
+ *
+ * `game` must be null or a handle from `po_game_new` that has not already been released.
+ */
+void po_game_free(struct PoGame *game);
+
+/**
+ * Parses a range such as "QQ+,AKs". On success `*out` holds a handle to release with
+ * `po_range_free`.
+ *
+ * # Safety
+ *
+ * `range` must be a null-terminated string and `out` must point to writable memory.
+ */
+enum PoStatus po_range_parse(const char *range, struct PoRange **out);
+
+/**
+ * Number of combinations in a range, or 0 for a null handle.
+ *
+ * # Safety
+ *
+ * `range` must be null or a live handle from `po_range_parse`.
+ */
+size_t po_range_len(const struct PoRange *range);
+
+/**
+ * Releases a range. Passing null does nothing.
+ *
+ * # Safety
+ *
+ * `range` must be null or a handle from `po_range_parse` that has not already been released.
+ */
+void po_range_free(struct PoRange *range);
+
+/**
+ * Equity of the game's hole cards against an opponent holding any hand in `range`.
+ *
+ * # Safety
+ *
+ * `game` and `range` must be live handles and `out` must point to writable memory.
+ */
+enum PoStatus po_game_equity(const struct PoGame *game,
+                             const struct PoRange *range,
+                             struct PoEquity *out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* POKER_ODDS_H */
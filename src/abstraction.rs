@@ -0,0 +1,325 @@
+//! Card abstraction for solvers and bots. Hands on one street are described by a histogram of
+//! their equity against a random hand over the rest of the board, and grouped into buckets by
+//! k-means under the earth mover's distance, so hands that tend to end up alike share a bucket
+//! even when their current equity differs. Hands that differ only by suits share a key, and
+//! bucket tables can be saved and loaded as JSON.
+
+use std::collections::HashMap;
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use crate::cards::{get_deck_without, Card, Game, SUITS};
+use crate::cfr::CardAbstraction;
+use crate::range::Range;
+
+/// The same key for every hand that is a relabelling of suits of another. The flop is unordered,
+/// while the turn and river keep their places.
+pub fn get_canonical_key(hole: &[Card], board: &[Card]) -> String {
+    let groups: Vec<&[Card]> = [hole, &board[..board.len().min(3)]].into_iter()
+        .chain(board.iter().skip(3).map(std::slice::from_ref))
+        .collect();
+    (0..SUITS.len()).permutations(SUITS.len())
+        .map(|permutation| {
+            let relabel = |card: &Card| {
+                let suit = SUITS.iter().position(|suit| *suit == card.suit()).map_or(SUITS.len(), |suit| permutation[suit]);
+                (card.value(), suit)
+            };
+            groups.iter()
+                .map(|group| {
+                    let mut cards: Vec<(u8, usize)> = group.iter().map(relabel).collect();
+                    cards.sort_unstable_by(|a, b| b.cmp(a));
+                    cards.iter().map(|(value, suit)| format!("{}{}", b"23456789TJQKA"[*value as usize - 2] as char, b"abcdx"[*suit] as char)).join("")
+                })
+                .join("|")
+        })
+        .min()
+        .expect("There are always suit permutations")
+}
+
+/// Equity of `hole` against a random hand on a complete board
+fn get_river_equity(hole: &[Card], board: &[Card], any: &Range) -> Result<f64, &'static str> {
+    let game = Game::build([hole[0], hole[1]], [board[0], board[1], board[2]], Some(board[3]), Some(board[4]))?;
+    Ok(game.get_equity_against_range(any)?.equity)
+}
+
+/// The share of runouts in each of `bins` equal ranges of river equity against a random hand.
+/// Every runout is counted when there are at most `samples`, and otherwise `samples` random
+/// runouts are drawn with `seed`.
+pub fn get_equity_histogram(hole: &[Card], board: &[Card], bins: usize, samples: usize, seed: u64) -> Result<Vec<f64>, &'static str> {
+    if hole.len() != 2 || ![0, 3, 4, 5].contains(&board.len()) {
+        return Err("Give two hole cards and a preflop, flop, turn or river board");
+    }
+    let used = [hole, board].concat();
+    if used.iter().enumerate().any(|(index, card)| used[..index].contains(card)) {
+        return Err("The same card cannot be used twice");
+    }
+    if bins == 0 || samples == 0 {
+        return Err("At least one bin and one sample are needed");
+    }
+    let mut deck = get_deck_without(&used);
+    let missing = 5 - board.len();
+    let runouts: u64 = (0..missing as u64).map(|index| deck.len() as u64 - index).product::<u64>()
+        / (1..=missing as u64).product::<u64>();
+    let any = Range::any();
+    let mut histogram = vec![0.0; bins];
+    let mut add = |runout: &[Card]| -> Result<(), &'static str> {
+        let equity = get_river_equity(hole, &[board, runout].concat(), &any)?;
+        histogram[((equity * bins as f64) as usize).min(bins - 1)] += 1.0;
+        Ok(())
+    };
+    if runouts <= samples as u64 {
+        for runout in deck.iter().copied().combinations(missing) {
+            add(&runout)?;
+        }
+    } else {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..samples {
+            let (runout, _) = deck.partial_shuffle(&mut rng, missing);
+            add(runout)?;
+        }
+    }
+    let total: f64 = histogram.iter().sum();
+    Ok(histogram.iter().map(|count| count / total).collect())
+}
+
+/// The earth mover's distance between two histograms over the same bins: how much mass has to
+/// move, and how far, to turn one into the other
+pub fn get_earth_movers_distance(a: &[f64], b: &[f64]) -> f64 {
+    let mut carried = 0.0;
+    let mut distance = 0.0;
+    for (a, b) in a.iter().zip(b) {
+        carried += a - b;
+        distance += carried.abs();
+    }
+    distance / a.len().max(1) as f64
+}
+
+/// Groups `histograms` into at most `clusters` clusters by k-means under the earth mover's
+/// distance, starting from k-means++ centres chosen with `seed`. Returns the centre of each
+/// cluster and the cluster of each histogram.
+pub fn get_clusters(histograms: &[Vec<f64>], clusters: usize, iterations: usize, seed: u64) -> (Vec<Vec<f64>>, Vec<usize>) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut centres: Vec<Vec<f64>> = Vec::new();
+    if let Some(first) = histograms.get(rng.random_range(0..histograms.len().max(1))) {
+        centres.push(first.clone());
+    }
+    let nearest = |centres: &[Vec<f64>], histogram: &[f64]| centres.iter().enumerate()
+        .map(|(index, centre)| (get_earth_movers_distance(histogram, centre), index))
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .expect("There is always a centre");
+    while centres.len() < clusters.min(histograms.len()) {
+        let weights: Vec<f64> = histograms.iter().map(|histogram| nearest(&centres, histogram).0.powi(2)).collect();
+        let total: f64 = weights.iter().sum();
+        if total == 0.0 {
+            break;
+        }
+        let mut remaining = rng.random::<f64>() * total;
+        let index = weights.iter().position(|weight| { remaining -= weight; remaining < 0.0 }).unwrap_or(weights.len() - 1);
+        centres.push(histograms[index].clone());
+    }
+    let mut assignments = vec![0; histograms.len()];
+    for _ in 0..iterations {
+        let next: Vec<usize> = histograms.iter().map(|histogram| nearest(&centres, histogram).1).collect();
+        let changed = next != assignments;
+        assignments = next;
+        for (index, centre) in centres.iter_mut().enumerate() {
+            let members: Vec<&Vec<f64>> = histograms.iter().zip(&assignments).filter(|(_, cluster)| **cluster == index).map(|(histogram, _)| histogram).collect();
+            if !members.is_empty() {
+                *centre = (0..centre.len()).map(|bin| members.iter().map(|histogram| histogram[bin]).sum::<f64>() / members.len() as f64).collect();
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    (centres, assignments)
+}
+
+fn get_mean_equity(histogram: &[f64]) -> f64 {
+    histogram.iter().enumerate().map(|(bin, share)| (bin as f64 + 0.5) / histogram.len() as f64 * share).sum()
+}
+
+/// Buckets for the hands on one street, numbered from the weakest on average to the strongest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BucketTable {
+    board_size: usize,
+    bins: usize,
+    samples: usize,
+    seed: u64,
+    centres: Vec<Vec<f64>>,
+    buckets: HashMap<String, usize>,
+}
+
+impl BucketTable {
+    /// Buckets `hands`, each hole cards and a board of the same size, into at most `buckets`
+    /// buckets using histograms with `bins` bins from `samples` runouts each
+    pub fn build(hands: &[(Vec<Card>, Vec<Card>)], buckets: usize, bins: usize, samples: usize, seed: u64) -> Result<BucketTable, &'static str> {
+        let Some((_, board)) = hands.first() else {
+            return Err("At least one hand is needed");
+        };
+        if buckets == 0 {
+            return Err("At least one bucket is needed");
+        }
+        if hands.iter().any(|(_, other)| other.len() != board.len()) {
+            return Err("Every hand must be on the same street");
+        }
+        let mut keys = Vec::new();
+        let mut histograms = Vec::new();
+        for (hole, board) in hands {
+            let key = get_canonical_key(hole, board);
+            if !keys.contains(&key) {
+                histograms.push(get_equity_histogram(hole, board, bins, samples, seed)?);
+                keys.push(key);
+            }
+        }
+        let (centres, assignments) = get_clusters(&histograms, buckets, 100, seed);
+        let mut order: Vec<usize> = (0..centres.len()).collect();
+        order.sort_by(|a, b| get_mean_equity(&centres[*a]).total_cmp(&get_mean_equity(&centres[*b])));
+        let mut renumber = vec![0; centres.len()];
+        for (bucket, cluster) in order.iter().enumerate() {
+            renumber[*cluster] = bucket;
+        }
+        Ok(BucketTable {
+            board_size: board.len(),
+            bins,
+            samples,
+            seed,
+            centres: order.iter().map(|cluster| centres[*cluster].clone()).collect(),
+            buckets: keys.into_iter().zip(assignments).map(|(key, cluster)| (key, renumber[cluster])).collect(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.centres.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.centres.is_empty()
+    }
+
+    /// The bucket of a hand, which for a hand not in the table is the one with the nearest
+    /// centre to its histogram
+    pub fn get_bucket(&self, hole: &[Card], board: &[Card]) -> Result<usize, &'static str> {
+        if board.len() != self.board_size {
+            return Err("The board is not on this table's street");
+        }
+        if let Some(bucket) = self.buckets.get(&get_canonical_key(hole, board)) {
+            return Ok(*bucket);
+        }
+        let histogram = get_equity_histogram(hole, board, self.bins, self.samples, self.seed)?;
+        let distances = self.centres.iter().map(|centre| get_earth_movers_distance(&histogram, centre));
+        Ok(distances.enumerate().min_by(|a, b| a.1.total_cmp(&b.1)).map_or(0, |(bucket, _)| bucket))
+    }
+
+    pub fn save(&self) -> String {
+        serde_json::to_string(self).expect("Bucket tables always serialise")
+    }
+
+    pub fn load(json: &str) -> Result<BucketTable, &'static str> {
+        let table: BucketTable = serde_json::from_str(json).map_err(|_| "Not a valid bucket table")?;
+        if table.centres.is_empty() || table.buckets.values().any(|bucket| *bucket >= table.centres.len()) {
+            return Err("Not a valid bucket table");
+        }
+        Ok(table)
+    }
+}
+
+impl CardAbstraction for BucketTable {
+    fn get_bucket(&self, hole: &[Card], board: &[Card]) -> Result<usize, &'static str> {
+        BucketTable::get_bucket(self, hole, board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+    use crate::cfr::{BucketedRiver, CfrSolver, CfrVariant};
+    use crate::range::Range;
+
+    #[test]
+    fn test_canonical_key() {
+        let key = |hole: &str, board: &str| get_canonical_key(&parse_cards(hole).unwrap(), &parse_cards(board).unwrap());
+        assert_eq!(key("AhKh", "Qh7d2c"), key("KsAs", "2d7cQs"));
+        assert_ne!(key("AhKh", "Qh7d2c"), key("AhKh", "Qd7h2c"));
+        // The turn is not part of the flop
+        assert_ne!(key("AhKd", "Qh7h2c3h"), key("AhKd", "Qh7h3h2c"));
+        assert_eq!(key("7s7c", ""), key("7h7d", ""));
+    }
+
+    #[test]
+    fn test_equity_histogram() {
+        let cards = parse_cards("AhAd7s5c2dKhKd").unwrap();
+        let river = get_equity_histogram(&cards[..2], &cards[2..7], 10, 100, 0).unwrap();
+        assert_eq!(river.iter().filter(|share| **share > 0.0).count(), 1);
+        let turn = get_equity_histogram(&cards[..2], &cards[2..6], 10, 100, 0).unwrap();
+        assert!((turn.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        // Aces stay well ahead of a random hand on every river
+        assert!((turn[8] + turn[9] - 1.0).abs() < 1e-9);
+        let flop = get_equity_histogram(&cards[..2], &cards[2..5], 10, 20, 0).unwrap();
+        assert_eq!(flop, get_equity_histogram(&cards[..2], &cards[2..5], 10, 20, 0).unwrap());
+        assert!(get_equity_histogram(&cards[..2], &cards[..3], 10, 20, 0).is_err());
+
+        let a = [0.5, 0.5, 0.0];
+        assert_eq!(get_earth_movers_distance(&a, &a), 0.0);
+        assert!(get_earth_movers_distance(&a, &[0.0, 0.5, 0.5]) < get_earth_movers_distance(&a, &[0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn test_bucket_table() {
+        let board = parse_cards("Ks9h4d2c7s").unwrap();
+        let hands: Vec<(Vec<Card>, Vec<Card>)> = Range::parse("AA, KQ, 98s, 65s, 32s").unwrap()
+            .combos_without(&board)
+            .iter()
+            .map(|combo| (combo.to_vec(), board.clone()))
+            .collect();
+        let table = BucketTable::build(&hands, 4, 20, 1, 0).unwrap();
+        assert_eq!(table.len(), 4);
+        let bucket = |hole: &str| table.get_bucket(&parse_cards(hole).unwrap(), &board).unwrap();
+        assert_eq!([bucket("6s5s"), bucket("3h2h"), bucket("9s8s"), bucket("AhAd"), bucket("KhQh")], [0, 1, 2, 3, 3]);
+        // A hand left out of the table goes to the bucket nearest its histogram
+        assert_eq!(bucket("AsKh"), 3);
+        assert_eq!(CardAbstraction::get_bucket(&table, &parse_cards("AcAd").unwrap(), &board), Ok(bucket("AhAd")));
+
+        let loaded = BucketTable::load(&table.save()).unwrap();
+        assert_eq!(loaded, table);
+        assert!(BucketTable::load("{}").is_err());
+        assert!(table.get_bucket(&parse_cards("AhAd").unwrap(), &board[..4]).is_err());
+    }
+
+    #[test]
+    fn test_solve_bucketed_river() {
+        // Aces are the nuts, while sixes and threes are both air and share a bucket
+        let board = parse_cards("Ks9h4d2c7s").unwrap();
+        let hands: Vec<(Vec<Card>, Vec<Card>)> = Range::parse("AA, KQ, 65s, 32s").unwrap()
+            .combos_without(&board)
+            .iter()
+            .map(|combo| (combo.to_vec(), board.clone()))
+            .collect();
+        let table = BucketTable::build(&hands, 2, 20, 1, 0).unwrap();
+        let bucket = |hole: &str| table.get_bucket(&parse_cards(hole).unwrap(), &board).unwrap();
+        let (nuts, air) = (bucket("AhAd"), bucket("6s5s"));
+        assert_eq!(bucket("3h2h"), air);
+        assert_eq!(bucket("KhQh"), nuts);
+
+        let ranges = [&Range::parse("AhAd, AsAc, 65s, 32s").unwrap(), &Range::parse("KQ").unwrap()];
+        let game = BucketedRiver::build(ranges, &board, &table).unwrap();
+        let mut solver = CfrSolver::new(game, CfrVariant::Plus, 0);
+        solver.train(1000);
+        assert!(solver.get_exploitability() < 0.01);
+        let strategy = solver.get_average_strategy();
+        // Two value combos bet with one bluff out of the seven air combos, and the bluff catcher
+        // calls half the time
+        assert!(strategy[&format!("{nuts}:")][1] > 0.99);
+        assert!((strategy[&format!("{air}:")][1] - 1.0 / 7.0).abs() < 0.02);
+        assert!((strategy[&format!("{nuts}:b")][1] - 0.5).abs() < 0.05);
+        assert!(BucketedRiver::build(ranges, &board[..4], &table).is_err());
+
+        // A table built for the flop cannot bucket river hands
+        let flop: Vec<(Vec<Card>, Vec<Card>)> = hands.iter().map(|(hole, board)| (hole.clone(), board[..3].to_vec())).collect();
+        let flop_table = BucketTable::build(&flop, 2, 10, 20, 0).unwrap();
+        assert_eq!(BucketedRiver::build(ranges, &board, &flop_table).err(), Some("The board is not on this table's street"));
+    }
+}
@@ -0,0 +1,240 @@
+//! All-in adjusted winnings over a session of hand histories. When a player is all-in before the
+//! river, the result of the runout is luck; replacing it with the player's equity share of the
+//! pot shows how the player would have done on average.
+
+use crate::cards::Card;
+use crate::history::{ActionKind, HandRecord, Round};
+
+/// One hand of an all-in adjusted report
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvRow {
+    pub hand: String,
+    /// Equity when the money went in, for hands with an all-in showdown
+    pub equity: Option<f64>,
+    /// Chips actually won, or lost as a negative number
+    pub won: f64,
+    /// Chips won with the pot shared by equity instead of the runout
+    pub ev_won: f64,
+    pub total_won: f64,
+    pub total_ev_won: f64,
+}
+
+/// Chips `player` put into the pot, less any uncalled bet returned
+fn get_invested(record: &HandRecord, player: &str) -> f64 {
+    let mut invested = 0.0;
+    let mut round = Round::Preflop;
+    let mut in_round = 0.0;
+    for action in record.actions.iter().filter(|action| action.player == player) {
+        if action.round != round {
+            invested += in_round;
+            in_round = 0.0;
+            round = action.round;
+        }
+        match action.kind {
+            ActionKind::PostAnte(amount) => invested += amount,
+            ActionKind::UncalledBetReturned(amount) => invested -= amount,
+            ActionKind::PostSmallBlind(amount) | ActionKind::PostBigBlind(amount) | ActionKind::Post(amount)
+            | ActionKind::Call(amount) | ActionKind::Bet(amount) => in_round += amount,
+            // A raise is to a total for the round, including any blind
+            ActionKind::Raise(_, to) => in_round = to,
+            _ => (),
+        }
+    }
+    invested + in_round
+}
+
+fn has_folded(record: &HandRecord, player: &str) -> bool {
+    record.actions.iter().any(|action| action.player == player && action.kind == ActionKind::Fold)
+}
+
+/// Hole cards of the players other than `player` who stayed in to the showdown and showed them
+fn get_live_opponents(record: &HandRecord, player: &str) -> Vec<Vec<Card>> {
+    record.players.iter()
+        .filter(|other| other.name != player && !has_folded(record, &other.name))
+        .filter_map(|other| other.hole.clone())
+        .collect()
+}
+
+/// The round the last chips went in, if anyone was all-in and `player` stayed in to a showdown
+/// against known hole cards
+fn get_all_in_round(record: &HandRecord, player: &str) -> Option<Round> {
+    if !record.actions.iter().any(|action| action.is_all_in) || has_folded(record, player) {
+        return None;
+    }
+    record.player(player)?.hole.as_ref()?;
+    if get_live_opponents(record, player).is_empty() {
+        return None;
+    }
+    let last_bet = record.actions.iter().rev().find(|action| matches!(
+        action.kind,
+        ActionKind::Bet(_) | ActionKind::Raise(_, _) | ActionKind::Call(_) | ActionKind::PostSmallBlind(_) | ActionKind::PostBigBlind(_) | ActionKind::Post(_)
+    ))?;
+    Some(last_bet.round)
+}
+
+/// `player`'s equity against the hole cards shown down by players still in the hand, on the
+/// board as it stood in `round`
+fn get_equity_at(record: &HandRecord, player: &str, round: Round) -> Result<f64, &'static str> {
+    let opponents = get_live_opponents(record, player);
+    let equities = if round >= Round::Flop {
+        record.to_game(player, round)?.get_equity(&opponents)?
+    } else {
+        let variant = record.variant().ok_or("The hand's game is not supported")?;
+        let mut hands = vec![record.player(player).and_then(|player| player.hole.clone()).ok_or("The player's hole cards are unknown")?];
+        hands.extend(opponents);
+        variant.get_equity(&hands, &[], &[])?
+    };
+    Ok(equities[0].equity)
+}
+
+/// Actual and all-in adjusted winnings for `player` in every hand they were dealt into, in the
+/// order given, with running totals. Hands without an all-in showdown count the same either
+/// way. Side pots are treated as a single pot shared by everyone who showed down.
+pub fn get_all_in_ev_report(records: &[HandRecord], player: &str) -> Result<Vec<EvRow>, &'static str> {
+    let mut rows: Vec<EvRow> = Vec::new();
+    let (mut total_won, mut total_ev_won) = (0.0, 0.0);
+    for record in records.iter().filter(|record| record.player(player).is_some()) {
+        let invested = get_invested(record, player);
+        let collected: f64 = record.winners.iter().filter(|(winner, _)| winner == player).map(|(_, amount)| amount).sum();
+        // Adding zero turns a negative zero, from a hand with nothing invested, into zero
+        let won = collected - invested + 0.0;
+        let (equity, ev_won) = match get_all_in_round(record, player) {
+            Some(round) => {
+                let equity = get_equity_at(record, player, round)?;
+                let pot: f64 = record.winners.iter().map(|(_, amount)| amount).sum();
+                (Some(equity), equity * pot - invested + 0.0)
+            }
+            None => (None, won),
+        };
+        total_won += won;
+        total_ev_won += ev_won;
+        rows.push(EvRow { hand: record.id.clone(), equity, won, ev_won, total_won, total_ev_won });
+    }
+    Ok(rows)
+}
+
+/// Writes a report as CSV with a header row, leaving the equity blank for hands without an
+/// all-in showdown
+pub fn to_csv(rows: &[EvRow]) -> String {
+    let mut csv = String::from("hand,equity,won,ev_won,total_won,total_ev_won\n");
+    for row in rows {
+        let equity = row.equity.map(|equity| format!("{equity:.4}")).unwrap_or_default();
+        csv += &format!(
+            "{},{},{:.2},{:.2},{:.2},{:.2}\n",
+            row.hand, equity, row.won, row.ev_won, row.total_won, row.total_ev_won
+        );
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::parse_hand_histories;
+
+    const SESSION: &str = "\
+PokerStars Hand #1:  Hold'em No Limit ($0.01/$0.02 USD) - 2020/01/01 12:00:00 ET
+Table 'Alpha' 6-max Seat #1 is the button
+Seat 1: Alice ($2.00 in chips)
+Seat 2: Bob ($3.00 in chips)
+Alice: posts small blind $0.01
+Bob: posts big blind $0.02
+*** HOLE CARDS ***
+Dealt to Alice [Ah Kd]
+Alice: raises $0.04 to $0.06
+Bob: calls $0.04
+*** FLOP *** [2h 7d Kc]
+Bob: bets $0.10
+Alice: raises $1.84 to $1.94 and is all-in
+Bob: calls $1.84
+*** TURN *** [2h 7d Kc] [Qh]
+*** RIVER *** [2h 7d Kc Qh] [Qc]
+*** SHOW DOWN ***
+Alice: shows [Ah Kd] (two pair, Kings and Queens)
+Bob: shows [Qs Qd] (four of a kind, Queens)
+Bob collected $3.96 from pot
+*** SUMMARY ***
+Total pot $4.00 | Rake $0.04
+
+PokerStars Hand #2:  Hold'em No Limit ($0.01/$0.02 USD) - 2020/01/01 12:01:00 ET
+Table 'Alpha' 6-max Seat #2 is the button
+Seat 1: Alice ($5.00 in chips)
+Seat 2: Bob ($1.00 in chips)
+Bob: posts small blind $0.01
+Alice: posts big blind $0.02
+*** HOLE CARDS ***
+Dealt to Alice [7h 2c]
+Bob: raises $0.04 to $0.06
+Alice: folds
+Uncalled bet ($0.04) returned to Bob
+Bob collected $0.04 from pot
+*** SUMMARY ***
+Total pot $0.04 | Rake $0
+
+PokerStars Hand #3:  Hold'em No Limit ($0.01/$0.02 USD) - 2020/01/01 12:02:00 ET
+Table 'Alpha' 6-max Seat #1 is the button
+Seat 1: Alice ($5.00 in chips)
+Seat 2: Bob ($1.00 in chips)
+Seat 3: Carol ($1.00 in chips)
+Bob: posts small blind $0.01
+Carol: posts big blind $0.02
+*** HOLE CARDS ***
+Dealt to Alice [As Ac]
+Alice: folds
+Bob: calls $0.01
+Carol: checks
+*** FLOP *** [2h 7d Kc]
+Bob: bets $0.98 and is all-in
+Carol: calls $0.98 and is all-in
+*** TURN *** [2h 7d Kc] [Qh]
+*** RIVER *** [2h 7d Kc Qh] [3c]
+*** SHOW DOWN ***
+Bob: shows [Ks Kd] (three of a kind, Kings)
+Carol: shows [Jh Jd] (a pair of Jacks)
+Bob collected $2 from pot
+*** SUMMARY ***
+Total pot $2 | Rake $0
+";
+
+    #[test]
+    fn test_get_all_in_ev_report() {
+        let records = parse_hand_histories(SESSION).unwrap();
+        let rows = get_all_in_ev_report(&records, "Alice").unwrap();
+        assert_eq!(rows.len(), 3);
+        // Top pair top kicker against queens on the flop loses only to a queen or runner-runner
+        let equity = rows[0].equity.unwrap();
+        assert!(equity > 0.85 && equity < 0.95);
+        assert!((rows[0].won + 2.0).abs() < 1e-9);
+        assert!((rows[0].ev_won - (equity * 3.96 - 2.0)).abs() < 1e-9);
+        assert_eq!(rows[1].equity, None);
+        assert!((rows[1].won + 0.02).abs() < 1e-9);
+        assert_eq!(rows[1].won, rows[1].ev_won);
+        assert!((rows[1].total_ev_won - (rows[0].ev_won + rows[1].ev_won)).abs() < 1e-9);
+        // Folding before the others got all-in leaves nothing to adjust
+        assert_eq!(rows[2].equity, None);
+        assert_eq!((rows[2].won, rows[2].ev_won), (0.0, 0.0));
+        assert!(rows[2].won.is_sign_positive());
+
+        // Bob took the big blind on the second hand
+        let bob = get_all_in_ev_report(&records, "Bob").unwrap();
+        assert!((bob[1].won - 0.02).abs() < 1e-9);
+        assert!((bob[0].equity.unwrap() + equity - 1.0).abs() < 1e-9);
+        // Only Carol's jacks count against Bob's kings, not the aces Alice folded
+        let bob_equity = bob[2].equity.unwrap();
+        assert!(bob_equity > 0.99 && bob_equity < 1.0);
+        let carol = get_all_in_ev_report(&records, "Carol").unwrap();
+        assert!((carol[0].equity.unwrap() + bob_equity - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let records = parse_hand_histories(SESSION).unwrap();
+        let csv = to_csv(&get_all_in_ev_report(&records, "Alice").unwrap());
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "hand,equity,won,ev_won,total_won,total_ev_won");
+        assert!(lines[1].starts_with("1,0.9"));
+        assert_eq!(lines[2].split(',').nth(1), Some(""));
+        assert!(lines[3].starts_with("3,,0.00,0.00,"));
+        assert_eq!(lines.len(), 4);
+    }
+}
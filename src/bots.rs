@@ -0,0 +1,303 @@
+//! Bots that play on a `Table`, and a harness that pits them against each other over many hands
+//! to measure their win rates. A few simple bots are included as baselines to benchmark
+//! strategies against.
+
+use rand::rngs::StdRng;
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::{Rng, SeedableRng};
+use crate::cards::{get_deck_without, Card, Game};
+use crate::history::{Action, Round};
+use crate::table::{LegalActions, PlayerAction, Seat, Table};
+
+/// What everyone at the table can see of a seat, which leaves out its hole cards
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SeatView<'a> {
+    pub name: &'a str,
+    pub stack: u64,
+    /// Chips put in during the current round
+    pub committed: u64,
+    pub folded: bool,
+    /// Whether the seat was dealt into the current hand
+    pub in_hand: bool,
+    pub all_in: bool,
+}
+
+impl SeatView<'_> {
+    /// Whether the seat is still contesting the pot
+    pub fn is_active(&self) -> bool {
+        self.in_hand && !self.folded
+    }
+}
+
+impl<'a> From<&'a Seat> for SeatView<'a> {
+    fn from(seat: &'a Seat) -> SeatView<'a> {
+        SeatView {
+            name: &seat.name,
+            stack: seat.stack,
+            committed: seat.committed,
+            folded: seat.folded,
+            in_hand: seat.in_hand,
+            all_in: seat.is_all_in(),
+        }
+    }
+}
+
+/// What the player to act can see: their own hole cards and everything public
+#[derive(Debug, Clone)]
+pub struct GameView<'a> {
+    pub seat: usize,
+    pub hole: &'a [Card],
+    pub board: &'a [Card],
+    pub round: Round,
+    pub pot: u64,
+    pub big_blind: u64,
+    /// Every seat, without anyone's hole cards
+    pub seats: Vec<SeatView<'a>>,
+    pub actions: &'a [Action],
+    pub legal: LegalActions,
+}
+
+impl GameView<'_> {
+    /// The view of the player to act, if anyone is to act
+    pub fn from_table(table: &Table) -> Option<GameView<'_>> {
+        let seat = table.to_act()?;
+        Some(GameView {
+            seat,
+            hole: &table.seats()[seat].hole,
+            board: table.board(),
+            round: table.round(),
+            pot: table.pot(),
+            big_blind: table.big_blind(),
+            seats: table.seats().iter().map(SeatView::from).collect(),
+            actions: table.actions(),
+            legal: table.get_legal_actions()?,
+        })
+    }
+
+    /// Players other than this one still contesting the pot
+    pub fn opponents(&self) -> usize {
+        self.seats.iter().filter(|seat| seat.is_active()).count() - 1
+    }
+
+    /// Checks when possible and calls otherwise
+    pub fn check_or_call(&self) -> PlayerAction {
+        if self.legal.check { PlayerAction::Check } else { PlayerAction::Call }
+    }
+
+    /// Checks when possible and folds otherwise
+    pub fn check_or_fold(&self) -> PlayerAction {
+        if self.legal.check { PlayerAction::Check } else { PlayerAction::Fold }
+    }
+}
+
+/// A way of playing. Every action returned must be legal in the view.
+pub trait Strategy {
+    fn act(&mut self, view: &GameView) -> PlayerAction;
+}
+
+/// Picks uniformly among the legal kinds of action, and then uniformly among the legal sizes
+pub struct RandomBot {
+    rng: StdRng,
+}
+
+impl RandomBot {
+    pub fn new(seed: u64) -> RandomBot {
+        RandomBot { rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+impl Strategy for RandomBot {
+    fn act(&mut self, view: &GameView) -> PlayerAction {
+        let legal = &view.legal;
+        let mut actions = Vec::new();
+        if legal.fold {
+            actions.push(PlayerAction::Fold);
+        }
+        if legal.check {
+            actions.push(PlayerAction::Check);
+        }
+        if legal.call.is_some() {
+            actions.push(PlayerAction::Call);
+        }
+        if let Some((min, max)) = legal.bet {
+            actions.push(PlayerAction::Bet(self.rng.random_range(min..=max)));
+        }
+        if let Some((min, max)) = legal.raise {
+            actions.push(PlayerAction::Raise(self.rng.random_range(min..=max)));
+        }
+        *actions.choose(&mut self.rng).expect("There is always a legal action")
+    }
+}
+
+/// Never folds and never raises
+pub struct AlwaysCallBot;
+
+impl Strategy for AlwaysCallBot {
+    fn act(&mut self, view: &GameView) -> PlayerAction {
+        view.check_or_call()
+    }
+}
+
+/// Makes the smallest bet or raise with at least `threshold` equity against random hands, and
+/// otherwise calls when the pot odds are good enough. Equity is estimated from `samples` random
+/// deals of the opponents' hands, and of the flop before it is dealt, with the rest of the
+/// board enumerated by `Game`.
+pub struct EquityThresholdBot {
+    threshold: f64,
+    samples: usize,
+    rng: StdRng,
+}
+
+impl EquityThresholdBot {
+    pub fn build(threshold: f64, samples: usize, seed: u64) -> Result<EquityThresholdBot, &'static str> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err("The equity threshold must be between 0 and 1");
+        }
+        if samples == 0 {
+            return Err("At least one sample is needed");
+        }
+        Ok(EquityThresholdBot { threshold, samples, rng: StdRng::seed_from_u64(seed) })
+    }
+
+    fn estimate_equity(&mut self, view: &GameView) -> f64 {
+        let mut known = view.hole.to_vec();
+        known.extend(view.board);
+        let mut deck = get_deck_without(&known);
+        let mut total = 0.0;
+        for _ in 0..self.samples {
+            deck.shuffle(&mut self.rng);
+            let mut cards = deck.iter().copied();
+            let opponents: Vec<Vec<Card>> = (0..view.opponents()).map(|_| cards.by_ref().take(2).collect()).collect();
+            let mut board = view.board.to_vec();
+            board.extend(cards.take(3usize.saturating_sub(board.len())));
+            let game = Game::build([view.hole[0], view.hole[1]], [board[0], board[1], board[2]], board.get(3).copied(), board.get(4).copied())
+                .expect("Sampled cards are never repeated");
+            total += game.get_equity(&opponents).expect("Sampled cards are never repeated")[0].equity;
+        }
+        total / self.samples as f64
+    }
+}
+
+impl Strategy for EquityThresholdBot {
+    fn act(&mut self, view: &GameView) -> PlayerAction {
+        let equity = self.estimate_equity(view);
+        if equity >= self.threshold {
+            if let Some((min, _)) = view.legal.bet {
+                return PlayerAction::Bet(min);
+            }
+            if let Some((min, _)) = view.legal.raise {
+                return PlayerAction::Raise(min);
+            }
+        }
+        match view.legal.call {
+            Some(call) if equity >= call as f64 / (view.pot + call) as f64 => PlayerAction::Call,
+            _ => view.check_or_fold(),
+        }
+    }
+}
+
+/// How one bot fared over a simulation, in big blinds
+#[derive(Debug, Clone, PartialEq)]
+pub struct BotResult {
+    /// Chips won, or lost as a negative number
+    pub won: i64,
+    pub bb_per_100: f64,
+    /// Standard deviation of the result of 100 hands
+    pub std_dev_per_100: f64,
+    /// 95% confidence interval for the true win rate in big blinds per 100 hands
+    pub confidence_interval: (f64, f64),
+}
+
+/// Plays `hands` hands between `bots`, seated in order with every stack reset to `stack` before
+/// each hand so no one goes broke. The button moves every hand. The same seed, and bots with
+/// the same seeds, always give the same results.
+pub fn simulate(
+    bots: &mut [Box<dyn Strategy>],
+    stack: u64,
+    small_blind: u64,
+    big_blind: u64,
+    hands: u64,
+    seed: u64,
+) -> Result<Vec<BotResult>, &'static str> {
+    let names: Vec<String> = (1..=bots.len()).map(|seat| format!("Bot {seat}")).collect();
+    let players: Vec<(&str, u64)> = names.iter().map(|name| (name.as_str(), stack)).collect();
+    let mut table = Table::build(&players, small_blind, big_blind, 0, seed)?;
+    if hands == 0 {
+        return Err("At least one hand is needed");
+    }
+    let mut won = vec![0i64; bots.len()];
+    let mut sum_of_squares = vec![0.0; bots.len()];
+    for _ in 0..hands {
+        for seat in 0..bots.len() {
+            table.set_stack(seat, stack)?;
+        }
+        table.start_hand()?;
+        while let Some(view) = GameView::from_table(&table) {
+            let action = bots[view.seat].act(&view);
+            table.act(action).map_err(|_| "A bot chose an illegal action")?;
+        }
+        for (seat, result) in table.seats().iter().enumerate() {
+            let result = result.stack as i64 - stack as i64;
+            won[seat] += result;
+            sum_of_squares[seat] += (result as f64 / big_blind as f64).powi(2);
+        }
+    }
+    let count = hands as f64;
+    let results = won.iter().zip(sum_of_squares)
+        .map(|(&won, sum_of_squares)| {
+            let mean = won as f64 / big_blind as f64 / count;
+            let variance = (sum_of_squares / count - mean * mean).max(0.0);
+            let std_dev_per_100 = variance.sqrt() * 10.0;
+            let margin = 1.96 * std_dev_per_100 / (count / 100.0).sqrt();
+            BotResult {
+                won,
+                bb_per_100: mean * 100.0,
+                std_dev_per_100,
+                confidence_interval: (mean * 100.0 - margin, mean * 100.0 + margin),
+            }
+        })
+        .collect();
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulate_is_zero_sum() {
+        let mut bots: Vec<Box<dyn Strategy>> = vec![Box::new(RandomBot::new(1)), Box::new(AlwaysCallBot), Box::new(RandomBot::new(2))];
+        let results = simulate(&mut bots, 200, 1, 2, 2000, 5).unwrap();
+        assert_eq!(results.iter().map(|result| result.won).sum::<i64>(), 0);
+        for result in &results {
+            assert!(result.confidence_interval.0 < result.bb_per_100 && result.bb_per_100 < result.confidence_interval.1);
+            assert!(result.std_dev_per_100 > 0.0);
+        }
+
+        let mut again: Vec<Box<dyn Strategy>> = vec![Box::new(RandomBot::new(1)), Box::new(AlwaysCallBot), Box::new(RandomBot::new(2))];
+        assert_eq!(simulate(&mut again, 200, 1, 2, 2000, 5).unwrap(), results);
+        assert!(simulate(&mut again, 200, 1, 2, 0, 5).is_err());
+    }
+
+    #[test]
+    fn test_equity_bot_beats_random_bot() {
+        let mut bots: Vec<Box<dyn Strategy>> = vec![Box::new(EquityThresholdBot::build(0.6, 4, 3).unwrap()), Box::new(RandomBot::new(4))];
+        let results = simulate(&mut bots, 100, 1, 2, 200, 6).unwrap();
+        assert!(results[0].bb_per_100 > 0.0);
+        assert_eq!(results[0].won, -results[1].won);
+        assert!(EquityThresholdBot::build(1.5, 4, 3).is_err());
+    }
+
+    #[test]
+    fn test_game_view() {
+        let mut table = Table::build(&[("A", 100), ("B", 100), ("C", 100)], 1, 2, 0, 1).unwrap();
+        assert!(GameView::from_table(&table).is_none());
+        table.start_hand().unwrap();
+        let view = GameView::from_table(&table).unwrap();
+        assert_eq!((view.seat, view.opponents(), view.pot, view.round), (0, 2, 3, Round::Preflop));
+        assert_eq!(view.hole.len(), 2);
+        assert_eq!(view.seats[1], SeatView { name: "B", stack: 99, committed: 1, folded: false, in_hand: true, all_in: false });
+        assert_eq!(view.check_or_call(), PlayerAction::Call);
+        assert_eq!(AlwaysCallBot.act(&view), PlayerAction::Call);
+    }
+}
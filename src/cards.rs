@@ -1,15 +1,37 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 use itertools::Itertools;
+use crate::low::{get_eight_or_better_low, get_omaha_eight_or_better_low, LowHand};
+use crate::range::Range;
+use crate::short_deck::get_short_deck_hand_rank;
+use crate::wild::{get_wild_hand_rank, WildCards};
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Suit {
     Spades,
     Diamonds,
     Clubs,
     Hearts,
+    /// The suit of the joker, which has no value of its own and is always wild
+    Joker,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+pub const SUITS: [Suit; 4] = [Suit::Spades, Suit::Diamonds, Suit::Clubs, Suit::Hearts];
+
+impl Suit {
+    fn index(&self) -> usize {
+        match self {
+            Suit::Spades => 0,
+            Suit::Diamonds => 1,
+            Suit::Clubs => 2,
+            Suit::Hearts => 3,
+            Suit::Joker => unreachable!("Jokers are ranked as wild cards"),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Card {
     suit: Suit,
     value: u8,
@@ -17,11 +39,90 @@ pub struct Card {
 
 impl Card {
     pub fn build(suit: Suit, value: u8) -> Result<Card, &'static str> {
+        if suit == Suit::Joker {
+            return Err("Jokers have no value; use Card::joker");
+        }
         if !(2..=14).contains(&value) {
             return Err("Value must be between 2 (Two) and 14 (Ace)");
         }
         Ok(Card { suit, value })
     }
+
+    /// The joker, which reports a value of 0
+    pub fn joker() -> Card {
+        Card { suit: Suit::Joker, value: 0 }
+    }
+
+    pub fn is_joker(&self) -> bool {
+        self.suit == Suit::Joker
+    }
+
+    pub fn suit(&self) -> Suit {
+        self.suit
+    }
+
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+}
+
+/// Parses cards written as a value followed by a suit, such as "Ah" or "Td", or "Jk" for the
+/// joker
+impl FromStr for Card {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Card, Self::Err> {
+        if s.eq_ignore_ascii_case("jk") {
+            return Ok(Card::joker());
+        }
+        let mut chars = s.chars();
+        let (Some(value), Some(suit), None) = (chars.next(), chars.next(), chars.next()) else {
+            return Err("Card must be a value followed by a suit, such as Ah");
+        };
+        let value = match value.to_ascii_uppercase() {
+            'T' => 10,
+            'J' => 11,
+            'Q' => 12,
+            'K' => 13,
+            'A' => 14,
+            digit @ '2'..='9' => digit as u8 - b'0',
+            _ => return Err("Card value must be one of 23456789TJQKA"),
+        };
+        let suit = match suit.to_ascii_lowercase() {
+            's' => Suit::Spades,
+            'd' => Suit::Diamonds,
+            'c' => Suit::Clubs,
+            'h' => Suit::Hearts,
+            _ => return Err("Card suit must be one of sdch"),
+        };
+        Card::build(suit, value)
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_joker() {
+            return write!(f, "Jk");
+        }
+        let value = b"23456789TJQKA"[self.value as usize - 2] as char;
+        let suit = match self.suit {
+            Suit::Spades => 's',
+            Suit::Diamonds => 'd',
+            Suit::Clubs => 'c',
+            Suit::Hearts => 'h',
+            Suit::Joker => unreachable!(),
+        };
+        write!(f, "{value}{suit}")
+    }
+}
+
+/// Parses a run of cards with no separators, such as "AhKd" or "Ts9s8c"
+pub fn parse_cards(s: &str) -> Result<Vec<Card>, &'static str> {
+    let s: Vec<char> = s.chars().filter(|c| !c.is_whitespace()).collect();
+    if !s.len().is_multiple_of(2) {
+        return Err("Cards must each be a value followed by a suit, such as AhKd");
+    }
+    s.chunks(2).map(|chunk| chunk.iter().collect::<String>().parse()).collect()
 }
 
 #[derive(Debug, PartialEq, PartialOrd, Eq, Hash)]
@@ -36,13 +137,17 @@ pub enum Hand {
     FourOfAKind(u8),
     StraightFlush(u8), // u8: High card
     RoyalFlush,
+    FiveOfAKind(u8), // Only possible with wild cards
 }
 
 fn is_all_same_value(values: &[u8]) -> bool {
     values.iter().all(|x| *x == values[0])
 }
 
-fn get_best_hand(hand: &[Card]) -> Hand {
+/// Names the hand made by exactly five cards. Use `get_hand_rank` for more cards and the
+/// `three_card` rankers for three.
+#[allow(clippy::redundant_pattern_matching, clippy::redundant_closure)]
+pub fn get_best_hand(hand: &[Card]) -> Hand {
     let mut hand = hand.to_vec();
     hand.sort_by(|a, b| a.value.partial_cmp(&b.value).unwrap());
     let values: Vec<u8> = hand.iter().map(|x| x.value).collect();
@@ -118,26 +223,732 @@ fn get_best_hand(hand: &[Card]) -> Hand {
     }
 }
 
+/// Strength of the best five cards in a hand, including kickers, so that any two hands can be
+/// compared directly. The category's place in the variant's ordering sits in the top bits, then
+/// the category itself, then the five deciding values.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HandRank(u32);
+
+impl HandRank {
+    pub(crate) fn new(category: u32, values: &[u8]) -> HandRank {
+        let mut rank = (category << 4) | category;
+        for index in 0..5 {
+            rank = (rank << 4) | *values.get(index).unwrap_or(&0) as u32;
+        }
+        HandRank(rank)
+    }
+
+    /// The category's place in the variant's ordering
+    pub(crate) fn order(&self) -> u32 {
+        self.0 >> 24
+    }
+
+    pub(crate) fn category(&self) -> u32 {
+        (self.0 >> 20) & 0xf
+    }
+
+    /// The same hand placed at `order` among the categories, for variants that rank the
+    /// categories differently
+    pub(crate) fn with_order(&self, order: u32) -> HandRank {
+        HandRank((order << 24) | (self.0 & 0xffffff))
+    }
+
+    fn value(&self, index: usize) -> u8 {
+        ((self.0 >> (16 - 4 * index)) & 0xf) as u8
+    }
+
+    /// The deciding values of the hand, most significant first
+    pub fn values(&self) -> Vec<u8> {
+        (0..5).map(|index| self.value(index)).filter(|value| *value != 0).collect()
+    }
+
+    pub fn as_u32(&self) -> u32 {
+        self.0
+    }
+
+    /// Unlike `get_best_hand`, a five-high straight reports 5 as its high card
+    pub fn hand(&self) -> Hand {
+        match self.category() {
+            0 => Hand::HighCard(self.value(0)),
+            1 => Hand::Pair(self.value(0)),
+            2 => Hand::TwoPair(self.value(0), self.value(1)),
+            3 => Hand::ThreeOfAKind(self.value(0)),
+            4 => Hand::Straight(self.value(0)),
+            5 => Hand::Flush(self.value(0)),
+            6 => Hand::FullHouse(self.value(0), self.value(1)),
+            7 => Hand::FourOfAKind(self.value(0)),
+            8 if self.value(0) == 14 => Hand::RoyalFlush,
+            8 => Hand::StraightFlush(self.value(0)),
+            _ => Hand::FiveOfAKind(self.value(0)),
+        }
+    }
+}
+
+fn get_straight_high_card(mask: u16) -> Option<u8> {
+    // An ace also plays low, below the two
+    let mask = if mask & (1 << 14) != 0 { mask | (1 << 1) } else { mask };
+    (5..=14u8).rev().find(|high| {
+        let run = 0b11111 << (high - 4);
+        mask & run == run
+    })
+}
+
+/// Ranks the best five-card hand that can be made from five or more cards. Any jokers are wild.
+pub fn get_hand_rank(cards: &[Card]) -> HandRank {
+    if cards.iter().any(|card| card.is_joker()) {
+        return get_wild_hand_rank(cards, WildCards::Jokers);
+    }
+    let mut counts = [0u8; 15];
+    let mut suit_masks = [0u16; 4];
+    for card in cards {
+        counts[card.value as usize] += 1;
+        suit_masks[card.suit.index()] |= 1 << card.value;
+    }
+    if let Some(mask) = suit_masks.into_iter().find(|mask| mask.count_ones() >= 5) {
+        if let Some(high) = get_straight_high_card(mask) {
+            return HandRank::new(8, &[high]);
+        }
+    }
+    // Values grouped by how many times they appear, most often then highest first
+    let mut groups: Vec<(u8, u8)> = (2..=14u8).rev()
+        .filter(|value| counts[*value as usize] > 0)
+        .map(|value| (counts[value as usize], value))
+        .collect();
+    groups.sort_by_key(|group| std::cmp::Reverse(group.0));
+    let kickers = |used: &[u8], n: usize| -> Vec<u8> {
+        let mut values = used.to_vec();
+        values.extend((2..=14u8).rev().filter(|value| counts[*value as usize] > 0 && !used.contains(value)).take(n));
+        values
+    };
+    if groups[0].0 >= 4 {
+        return HandRank::new(7, &kickers(&[groups[0].1], 1));
+    }
+    if groups[0].0 == 3 {
+        if let Some(pair) = groups[1..].iter().filter(|group| group.0 >= 2).map(|group| group.1).max() {
+            return HandRank::new(6, &[groups[0].1, pair]);
+        }
+    }
+    if let Some(mask) = suit_masks.into_iter().find(|mask| mask.count_ones() >= 5) {
+        let values: Vec<u8> = (2..=14u8).rev().filter(|value| mask & (1 << value) != 0).take(5).collect();
+        return HandRank::new(5, &values);
+    }
+    let value_mask = (2..=14u8).filter(|value| counts[*value as usize] > 0).fold(0u16, |mask, value| mask | (1 << value));
+    if let Some(high) = get_straight_high_card(value_mask) {
+        return HandRank::new(4, &[high]);
+    }
+    match (groups[0].0, groups.get(1).map(|group| group.0)) {
+        (3, _) => HandRank::new(3, &kickers(&[groups[0].1], 2)),
+        (2, Some(2)) => HandRank::new(2, &kickers(&[groups[0].1, groups[1].1], 1)),
+        (2, _) => HandRank::new(1, &kickers(&[groups[0].1], 3)),
+        _ => HandRank::new(0, &kickers(&[], 5)),
+    }
+}
+
+/// Share of the pot a hand can expect, over every way the board can run out
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Equity {
+    /// Fraction of runouts won outright
+    pub win: f64,
+    /// Fraction of runouts where the pot is split
+    pub tie: f64,
+    /// Expected fraction of the pot, counting a split as an equal share
+    pub equity: f64,
+}
+
+/// Calculates the equity of each hand by enumerating every runout of the remaining board cards.
+/// Each hand makes its best five cards from its own cards and the board.
+pub fn get_equity(hands: &[Vec<Card>], board: &[Card], dead_cards: &[Card]) -> Result<Vec<Equity>, &'static str> {
+    get_equity_with(hands, board, dead_cards, &Variant::Holdem.deck(), 5, |hand, board| Variant::Holdem.get_hand_rank(hand, board))
+}
+
+/// Share of a split pot a hand can expect, separating what it wins with its high hand from what
+/// it wins with its low hand
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SplitEquity {
+    /// Expected fraction of the whole pot
+    pub equity: f64,
+    /// Expected fraction of the whole pot won with the high hand
+    pub high: f64,
+    /// Expected fraction of the whole pot won with the low hand
+    pub low: f64,
+    /// Fraction of runouts taking the whole pot alone
+    pub scoop: f64,
+    /// Fraction of runouts left with only a quarter of the pot
+    pub quartered: f64,
+}
+
+/// Visits every complete board that can run out from `board`, after checking that no card is
+/// used twice. Returns the number of runouts visited.
+pub(crate) fn for_each_runout(
+    hands: &[Vec<Card>],
+    board: &[Card],
+    dead_cards: &[Card],
+    deck: &[Card],
+    board_size: usize,
+    mut visit: impl FnMut(&[Card]),
+) -> Result<usize, &'static str> {
+    if hands.len() < 2 {
+        return Err("At least two hands are needed to calculate equity");
+    }
+    if board.len() > board_size {
+        return Err("Board has too many cards");
+    }
+    let mut used_cards: Vec<Card> = hands.concat();
+    used_cards.extend(board);
+    used_cards.extend(dead_cards);
+    if used_cards.iter().enumerate().any(|(index, card)| used_cards[..index].contains(card)) {
+        return Err("The same card cannot be used twice");
+    }
+    if used_cards.iter().any(|card| !deck.contains(card)) {
+        return Err("Every card must come from the variant's deck");
+    }
+    let unused_cards: Vec<Card> = deck.iter().filter(|card| !used_cards.contains(card)).copied().collect();
+    let mut runouts = 0;
+    for final_cards in unused_cards.into_iter().combinations(board_size - board.len()) {
+        let mut full_board = board.to_vec();
+        full_board.extend(final_cards);
+        visit(&full_board);
+        runouts += 1;
+    }
+    Ok(runouts)
+}
+
+/// Splits a pot equally between the hands holding the best rank
+pub(crate) fn split_between<R: Ord>(ranks: &[R]) -> Vec<f64> {
+    let best = ranks.iter().max().unwrap();
+    let winners = ranks.iter().filter(|rank| *rank == best).count() as f64;
+    ranks.iter().map(|rank| if rank == best { 1.0 / winners } else { 0.0 }).collect()
+}
+
+/// Shared enumeration behind the equity calculations, with `rank` deciding how a hand plays a
+/// complete board
+pub(crate) fn get_equity_with<R: Ord>(
+    hands: &[Vec<Card>],
+    board: &[Card],
+    dead_cards: &[Card],
+    deck: &[Card],
+    board_size: usize,
+    rank: impl Fn(&[Card], &[Card]) -> R,
+) -> Result<Vec<Equity>, &'static str> {
+    get_equity_from_shares(hands, board, dead_cards, deck, board_size, |full_board| {
+        let ranks: Vec<R> = hands.iter().map(|hand| rank(hand, full_board)).collect();
+        split_between(&ranks)
+    })
+}
+
+/// Shared enumeration behind the equity calculations, with `shares` deciding what fraction of
+/// the pot each hand takes on a complete board
+pub(crate) fn get_equity_from_shares(
+    hands: &[Vec<Card>],
+    board: &[Card],
+    dead_cards: &[Card],
+    deck: &[Card],
+    board_size: usize,
+    shares: impl Fn(&[Card]) -> Vec<f64>,
+) -> Result<Vec<Equity>, &'static str> {
+    let mut totals = EquityTotals::new(hands.len());
+    for_each_runout(hands, board, dead_cards, deck, board_size, |full_board| totals.add(&shares(full_board)))?;
+    Ok(totals.into_equity())
+}
+
+/// Visits every five card hand `cards` can be completed to with cards from `unused_cards`
+pub(crate) fn for_each_five_card_hand(cards: &[Card], unused_cards: &[Card], mut visit: impl FnMut(&[Card])) {
+    let remaining_length = 5 - cards.len();
+    for final_cards in unused_cards.iter().copied().combinations(remaining_length) {
+        let mut this_hand = cards.to_vec();
+        this_hand.extend(final_cards);
+        visit(&this_hand);
+    }
+}
+
+/// Exact equity by enumeration is refused above this many deals, which would take too long;
+/// callers are pointed to their sampled estimate instead
+pub(crate) const MAX_EXACT_DEALS: u64 = 1_000_000;
+
+/// Number of ways to deal `counts[i]` cards to each player `i` from `cards` cards, saturating
+/// rather than overflowing
+pub(crate) fn count_deals(mut cards: usize, counts: &[usize]) -> u64 {
+    let mut deals: u64 = 1;
+    for count in counts {
+        let ways = (0..*count as u64).fold(1, |ways, index| ways * (cards as u64 - index) / (index + 1));
+        deals = deals.saturating_mul(ways);
+        cards -= count;
+    }
+    deals
+}
+
+/// Visits every way of dealing `counts[i]` more cards to each player `i` from `unused_cards`
+pub(crate) fn for_each_deal(unused_cards: &[Card], counts: &[usize], visit: &mut impl FnMut(&[Vec<Card>])) {
+    fn deal_next(unused_cards: &[Card], counts: &[usize], dealt: &mut Vec<Vec<Card>>, visit: &mut impl FnMut(&[Vec<Card>])) {
+        let Some(count) = counts.get(dealt.len()) else {
+            visit(dealt);
+            return;
+        };
+        for cards in unused_cards.iter().copied().combinations(*count) {
+            let remaining: Vec<Card> = unused_cards.iter().filter(|card| !cards.contains(card)).copied().collect();
+            dealt.push(cards);
+            deal_next(&remaining, counts, dealt, visit);
+            dealt.pop();
+        }
+    }
+    deal_next(unused_cards, counts, &mut Vec::new(), visit);
+}
+
+/// Deals `counts[i]` cards to each player `i` from the front of `shuffled_cards`
+pub(crate) fn deal_from(shuffled_cards: &[Card], counts: &[usize]) -> Vec<Vec<Card>> {
+    let mut dealt = Vec::new();
+    let mut next = 0;
+    for count in counts {
+        dealt.push(shuffled_cards[next..next + count].to_vec());
+        next += count;
+    }
+    dealt
+}
+
+/// Running tally of pot shares across deals, turned into `Equity` at the end
+pub(crate) struct EquityTotals {
+    wins: Vec<f64>,
+    ties: Vec<f64>,
+    shares: Vec<f64>,
+    deals: f64,
+}
+
+impl EquityTotals {
+    pub(crate) fn new(players: usize) -> EquityTotals {
+        EquityTotals { wins: vec![0.0; players], ties: vec![0.0; players], shares: vec![0.0; players], deals: 0.0 }
+    }
+
+    pub(crate) fn add(&mut self, shares: &[f64]) {
+        for (index, share) in shares.iter().enumerate() {
+            if *share == 1.0 {
+                self.wins[index] += 1.0;
+            } else if *share > 0.0 {
+                self.ties[index] += 1.0;
+            }
+            self.shares[index] += share;
+        }
+        self.deals += 1.0;
+    }
+
+    pub(crate) fn into_equity(self) -> Vec<Equity> {
+        (0..self.shares.len()).map(|index| Equity {
+            win: self.wins[index] / self.deals,
+            tie: self.ties[index] / self.deals,
+            equity: self.shares[index] / self.deals,
+        }).collect()
+    }
+}
+
+/// Every card in a standard deck that is not in `used_cards`
+pub fn get_deck_without(used_cards: &[Card]) -> Vec<Card> {
+    let mut unused_cards: Vec<Card> = Vec::new();
+    for suit in SUITS {
+        for value in 2..=14 {
+            let current_card = Card { suit, value };
+            if !used_cards.contains(&current_card) {
+                unused_cards.push(current_card);
+            }
+        }
+    }
+    unused_cards
+}
+
+/// The rules deciding how many hole cards each player holds and how they combine with the board
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Variant {
+    /// Two hole cards, with the best five of the hole cards and board playing
+    Holdem,
+    /// Pot-Limit Omaha with four to six hole cards, of which exactly two must play with exactly
+    /// three from the board
+    Omaha,
+    /// Hold'em with the pot split between the best high hand and the best eight-or-better low
+    HoldemHiLo,
+    /// Omaha with the pot split between the best high hand and the best eight-or-better low,
+    /// each using exactly two hole cards
+    OmahaHiLo,
+    /// Hold'em dealt from a 36 card deck without the twos through fives, where a flush beats a
+    /// full house and A-6-7-8-9 is the lowest straight. Rooms differ on whether three of a kind
+    /// beats a straight.
+    ShortDeck { trips_beat_straight: bool },
+    /// Hold'em where each player is dealt three hole cards and discards one before the flop
+    Pineapple,
+    /// Pineapple where the discard is made after the flop instead
+    CrazyPineapple,
+}
+
+/// Equity of every hand in a pot after one of the hole cards is discarded
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscardEquity {
+    pub discard: Card,
+    /// This hand's equity first, then one entry per opponent
+    pub equities: Vec<Equity>,
+}
+
+/// How strong two hole cards are against one random hand, now and by the river
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HandStrength {
+    /// Share of opponent hands beaten on the current board, counting ties as half
+    pub hand_strength: f64,
+    /// Chance of ending ahead by the river when behind now, counting ties as half
+    pub positive_potential: f64,
+    /// Chance of ending behind by the river when ahead now, counting ties as half
+    pub negative_potential: f64,
+    /// Effective hand strength: the hand strength adjusted by both potentials
+    pub ehs: f64,
+    /// Average over river boards of the square of the hand strength, which rewards draws that
+    /// often end very strong over hands that stay middling
+    pub ehs_squared: f64,
+}
+
+/// Ranks the best Omaha hand, which must use exactly two hole cards and three board cards
+pub fn get_omaha_hand_rank(hole: &[Card], board: &[Card]) -> HandRank {
+    let mut best = HandRank(0);
+    for two_of_hole in hole.iter().combinations(2) {
+        for three_of_board in board.iter().combinations(3) {
+            let hand: Vec<Card> = two_of_hole.iter().chain(three_of_board.iter()).map(|card| **card).collect();
+            best = best.max(get_hand_rank(&hand));
+        }
+    }
+    best
+}
+
+impl Variant {
+    /// Whether a player may hold this many hole cards
+    pub fn is_valid_hole_size(&self, size: usize) -> bool {
+        match self {
+            Variant::Holdem | Variant::HoldemHiLo | Variant::ShortDeck { .. } => size == 2,
+            Variant::Omaha | Variant::OmahaHiLo => (4..=6).contains(&size),
+            Variant::Pineapple | Variant::CrazyPineapple => size == 2 || size == 3,
+        }
+    }
+
+    /// Whether a hand of this many hole cards can go to showdown, which in the Pineapple variants
+    /// is only after the discard
+    pub fn is_valid_showdown_size(&self, size: usize) -> bool {
+        match self {
+            Variant::Pineapple | Variant::CrazyPineapple => size == 2,
+            _ => self.is_valid_hole_size(size),
+        }
+    }
+
+    /// The last board size at which a Pineapple hand may still hold three cards, or `None` for
+    /// variants without a discard
+    pub fn get_discard_board_size(&self) -> Option<usize> {
+        match self {
+            Variant::Pineapple => Some(0),
+            Variant::CrazyPineapple => Some(3),
+            _ => None,
+        }
+    }
+
+    /// Whether the pot is split between a high hand and a low hand
+    pub fn is_split(&self) -> bool {
+        matches!(self, Variant::HoldemHiLo | Variant::OmahaHiLo)
+    }
+
+    /// Ranks the best hand that `hole` can make with a complete `board` under this variant
+    pub fn get_hand_rank(&self, hole: &[Card], board: &[Card]) -> HandRank {
+        match self {
+            Variant::Holdem | Variant::HoldemHiLo | Variant::Pineapple | Variant::CrazyPineapple => {
+                let mut cards = hole.to_vec();
+                cards.extend(board);
+                get_hand_rank(&cards)
+            }
+            Variant::Omaha | Variant::OmahaHiLo => get_omaha_hand_rank(hole, board),
+            Variant::ShortDeck { trips_beat_straight } => {
+                let mut cards = hole.to_vec();
+                cards.extend(board);
+                get_short_deck_hand_rank(&cards, *trips_beat_straight)
+            }
+        }
+    }
+
+    /// The cards this variant is dealt from
+    pub fn deck(&self) -> Vec<Card> {
+        let lowest = match self {
+            Variant::ShortDeck { .. } => 6,
+            _ => 2,
+        };
+        get_deck_without(&[]).into_iter().filter(|card| card.value >= lowest).collect()
+    }
+
+    /// Every card in this variant's deck that is not in `used_cards`
+    pub fn get_deck_without(&self, used_cards: &[Card]) -> Vec<Card> {
+        self.deck().into_iter().filter(|card| !used_cards.contains(card)).collect()
+    }
+
+    /// The best qualifying low that `hole` can make with a complete `board`, if this variant
+    /// plays for low
+    pub fn get_low(&self, hole: &[Card], board: &[Card]) -> Option<LowHand> {
+        match self {
+            Variant::Holdem | Variant::Omaha | Variant::ShortDeck { .. } | Variant::Pineapple | Variant::CrazyPineapple => None,
+            Variant::HoldemHiLo => {
+                let mut cards = hole.to_vec();
+                cards.extend(board);
+                get_eight_or_better_low(&cards)
+            }
+            Variant::OmahaHiLo => get_omaha_eight_or_better_low(hole, board),
+        }
+    }
+
+    /// Fractions of the pot each hand wins with its high hand and with its low hand on a
+    /// complete board. Without a qualifying low, the high hand takes the whole pot.
+    pub fn get_pot_shares(&self, hands: &[Vec<Card>], board: &[Card]) -> Vec<(f64, f64)> {
+        let high = split_between(&hands.iter().map(|hand| self.get_hand_rank(hand, board)).collect::<Vec<HandRank>>());
+        let lows: Vec<Option<LowHand>> = hands.iter().map(|hand| self.get_low(hand, board)).collect();
+        if lows.iter().all(|low| low.is_none()) {
+            return high.into_iter().map(|share| (share, 0.0)).collect();
+        }
+        let low = split_between(&lows);
+        high.into_iter().zip(low).map(|(high, low)| (high / 2.0, low / 2.0)).collect()
+    }
+
+    /// Calculates the equity of each hand under this variant by enumerating every runout of the
+    /// remaining board cards
+    pub fn get_equity(&self, hands: &[Vec<Card>], board: &[Card], dead_cards: &[Card]) -> Result<Vec<Equity>, &'static str> {
+        if hands.iter().any(|hand| !self.is_valid_showdown_size(hand.len())) {
+            return Err("Every hand must have the number of hole cards the variant plays");
+        }
+        get_equity_from_shares(hands, board, dead_cards, &self.deck(), 5, |board| {
+            self.get_pot_shares(hands, board).into_iter().map(|(high, low)| high + low).collect()
+        })
+    }
+
+    /// Calculates the split-pot equity of each hand under this variant by enumerating every
+    /// runout of the remaining board cards. Variants without a low report it as zero.
+    pub fn get_split_equity(&self, hands: &[Vec<Card>], board: &[Card], dead_cards: &[Card]) -> Result<Vec<SplitEquity>, &'static str> {
+        if hands.iter().any(|hand| !self.is_valid_showdown_size(hand.len())) {
+            return Err("Every hand must have the number of hole cards the variant plays");
+        }
+        let mut totals = vec![SplitEquity { equity: 0.0, high: 0.0, low: 0.0, scoop: 0.0, quartered: 0.0 }; hands.len()];
+        let runouts = for_each_runout(hands, board, dead_cards, &self.deck(), 5, |board| {
+            for (total, (high, low)) in totals.iter_mut().zip(self.get_pot_shares(hands, board)) {
+                total.high += high;
+                total.low += low;
+                if high + low == 1.0 {
+                    total.scoop += 1.0;
+                } else if high + low == 0.25 {
+                    total.quartered += 1.0;
+                }
+            }
+        })? as f64;
+        Ok(totals.into_iter().map(|total| SplitEquity {
+            equity: (total.high + total.low) / runouts,
+            high: total.high / runouts,
+            low: total.low / runouts,
+            scoop: total.scoop / runouts,
+            quartered: total.quartered / runouts,
+        }).collect())
+    }
+
+    /// Equity of a three card Pineapple `hand` against opponents who have already discarded, for
+    /// each card it could discard, in the order of `hand`. The discard is out of play.
+    pub fn get_discard_equities(&self, hand: &[Card], opponents: &[Vec<Card>], board: &[Card], dead_cards: &[Card]) -> Result<Vec<DiscardEquity>, &'static str> {
+        match self.get_discard_board_size() {
+            None => return Err("Only the Pineapple variants discard"),
+            Some(size) if board.len() > size => return Err("The discard has already been made by this street"),
+            _ => (),
+        }
+        if hand.len() != 3 {
+            return Err("A Pineapple hand discards from three hole cards");
+        }
+        hand.iter().map(|discard| {
+            let mut hands = vec![hand.iter().filter(|card| *card != discard).copied().collect::<Vec<Card>>()];
+            hands.extend(opponents.iter().cloned());
+            let mut dead_cards = dead_cards.to_vec();
+            dead_cards.push(*discard);
+            let equities = self.get_equity(&hands, board, &dead_cards)?;
+            Ok(DiscardEquity { discard: *discard, equities })
+        }).collect()
+    }
+}
+
 pub struct Game {
-    hole: [Card; 2],
+    variant: Variant,
+    hole: Vec<Card>,
     flop: [Card; 3],
     turn: Option<Card>,
     river: Option<Card>,
 }
 
 impl Game {
+    pub fn build(hole: [Card; 2], flop: [Card; 3], turn: Option<Card>, river: Option<Card>) -> Result<Game, &'static str> {
+        Game::build_variant(Variant::Holdem, &hole, flop, turn, river)
+    }
+
+    pub fn build_variant(variant: Variant, hole: &[Card], flop: [Card; 3], turn: Option<Card>, river: Option<Card>) -> Result<Game, &'static str> {
+        if !variant.is_valid_hole_size(hole.len()) {
+            return Err("Hole must have the number of cards the variant deals");
+        }
+        if turn.is_none() && river.is_some() {
+            return Err("River cannot be dealt before the turn");
+        }
+        if hole.len() == 3 && turn.is_some() {
+            return Err("Pineapple hands must discard before the turn");
+        }
+        let game = Game { variant, hole: hole.to_vec(), flop, turn, river };
+        let used_cards = game.get_used_cards();
+        if used_cards.iter().enumerate().any(|(index, card)| used_cards[..index].contains(card)) {
+            return Err("The same card cannot be used twice");
+        }
+        let deck = variant.deck();
+        if used_cards.iter().any(|card| !deck.contains(card)) {
+            return Err("Every card must come from the variant's deck");
+        }
+        Ok(game)
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn hole(&self) -> &[Card] {
+        &self.hole
+    }
+
+    /// The community cards dealt so far
+    pub fn board(&self) -> Vec<Card> {
+        let mut board = self.flop.to_vec();
+        board.extend(self.turn);
+        board.extend(self.river);
+        board
+    }
+
+    /// Equity of the hole cards against each opponent's known hole cards. The first entry is
+    /// for this game's hole cards, followed by one entry per opponent.
+    pub fn get_equity(&self, opponents: &[Vec<Card>]) -> Result<Vec<Equity>, &'static str> {
+        let mut hands = vec![self.hole.clone()];
+        hands.extend(opponents.iter().cloned());
+        self.variant.get_equity(&hands, &self.board(), &[])
+    }
+
+    /// Split-pot equity of the hole cards against each opponent's known hole cards, in the same
+    /// order as `get_equity`
+    pub fn get_split_equity(&self, opponents: &[Vec<Card>]) -> Result<Vec<SplitEquity>, &'static str> {
+        let mut hands = vec![self.hole.clone()];
+        hands.extend(opponents.iter().cloned());
+        self.variant.get_split_equity(&hands, &self.board(), &[])
+    }
+
+    /// Equity after discarding `discard` from three Pineapple hole cards, in the same order as
+    /// `get_equity`. The discard is out of play.
+    pub fn get_equity_with_discard(&self, discard: Card, opponents: &[Vec<Card>]) -> Result<Vec<Equity>, &'static str> {
+        if self.hole.len() != 3 || !self.hole.contains(&discard) {
+            return Err("The discard must be one of three Pineapple hole cards");
+        }
+        let mut hands = vec![self.hole.iter().filter(|card| **card != discard).copied().collect::<Vec<Card>>()];
+        hands.extend(opponents.iter().cloned());
+        self.variant.get_equity(&hands, &self.board(), &[discard])
+    }
+
+    /// Equity of three Pineapple hole cards against each opponent's known hole cards for every
+    /// possible discard, in the order of the hole cards. In Pineapple, where the discard is made
+    /// before the flop, this is how each discard fares with this game's board.
+    pub fn get_discard_equities(&self, opponents: &[Vec<Card>]) -> Result<Vec<DiscardEquity>, &'static str> {
+        self.hole.iter()
+            .map(|discard| Ok(DiscardEquity { discard: *discard, equities: self.get_equity_with_discard(*discard, opponents)? }))
+            .collect()
+    }
+
+    /// The discard from three Pineapple hole cards that gives the most equity
+    pub fn get_best_discard(&self, opponents: &[Vec<Card>]) -> Result<DiscardEquity, &'static str> {
+        let discards = self.get_discard_equities(opponents)?;
+        Ok(discards.into_iter().max_by(|a, b| a.equities[0].equity.total_cmp(&b.equities[0].equity)).unwrap())
+    }
+
+    /// Equity of the hole cards against a single opponent holding any combination in `range`,
+    /// with every combination not blocked by a known card equally likely. Ranges only describe
+    /// Hold'em hands.
+    pub fn get_equity_against_range(&self, range: &Range) -> Result<Equity, &'static str> {
+        if !self.variant.is_valid_hole_size(2) {
+            return Err("Ranges are only supported for Hold'em");
+        }
+        let deck = self.get_unused_cards(&self.get_used_cards());
+        let combos: Vec<[Card; 2]> = range.combos().iter()
+            .filter(|combo| deck.contains(&combo[0]) && deck.contains(&combo[1]))
+            .copied()
+            .collect();
+        if combos.is_empty() {
+            return Err("Every combination in the range is blocked by a known card");
+        }
+        let mut total = Equity { win: 0.0, tie: 0.0, equity: 0.0 };
+        for combo in &combos {
+            let equity = self.get_equity(&[combo.to_vec()])?[0];
+            total.win += equity.win;
+            total.tie += equity.tie;
+            total.equity += equity.equity;
+        }
+        let count = combos.len() as f64;
+        Ok(Equity { win: total.win / count, tie: total.tie / count, equity: total.equity / count })
+    }
+
+    /// Strength and potential of two hole cards against every hand one opponent could hold,
+    /// enumerating the opponent's hands and every runout of the board
+    pub fn get_hand_strength(&self) -> Result<HandStrength, &'static str> {
+        if self.hole.len() != 2 || !self.variant.is_valid_hole_size(2) {
+            return Err("Hand strength is only supported for two hole cards");
+        }
+        let board = self.board();
+        let deck = self.get_unused_cards(&self.get_used_cards());
+        let rank = |hole: &[Card], board: &[Card]| self.variant.get_hand_rank(hole, board);
+        // 0 ahead, 1 tied, 2 behind
+        let compare = |hero: HandRank, opponent: HandRank| match hero.cmp(&opponent) {
+            std::cmp::Ordering::Greater => 0,
+            std::cmp::Ordering::Equal => 1,
+            std::cmp::Ordering::Less => 2,
+        };
+        let hero_now = rank(&self.hole, &board);
+        let mut now: HashMap<[Card; 2], usize> = HashMap::new();
+        let mut counts = [0.0; 3];
+        for opponent in deck.iter().copied().combinations(2) {
+            let state = compare(hero_now, rank(&opponent, &board));
+            counts[state] += 1.0;
+            now.insert([opponent[0], opponent[1]], state);
+        }
+        let hand_strength = (counts[0] + counts[1] / 2.0) / counts.iter().sum::<f64>();
+
+        // Counts of going from each state now to each state on the river
+        let mut transitions = [[0.0; 3]; 3];
+        let mut squares = 0.0;
+        let mut runouts = 0.0;
+        for runout in deck.iter().copied().combinations(5 - board.len()) {
+            let final_board = [board.clone(), runout.clone()].concat();
+            let hero_final = rank(&self.hole, &final_board);
+            let mut river = [0.0; 3];
+            for opponent in deck.iter().filter(|card| !runout.contains(card)).copied().combinations(2) {
+                let state = compare(hero_final, rank(&opponent, &final_board));
+                river[state] += 1.0;
+                transitions[now[&[opponent[0], opponent[1]]]][state] += 1.0;
+            }
+            squares += ((river[0] + river[1] / 2.0) / river.iter().sum::<f64>()).powi(2);
+            runouts += 1.0;
+        }
+        let totals = transitions.map(|row| row.iter().sum::<f64>());
+        let ratio = |gained: f64, total: f64| if total > 0.0 { gained / total } else { 0.0 };
+        let positive_potential = ratio(
+            transitions[2][0] + transitions[2][1] / 2.0 + transitions[1][0] / 2.0,
+            totals[2] + totals[1] / 2.0,
+        );
+        let negative_potential = ratio(
+            transitions[0][2] + transitions[1][2] / 2.0 + transitions[0][1] / 2.0,
+            totals[0] + totals[1] / 2.0,
+        );
+        Ok(HandStrength {
+            hand_strength,
+            positive_potential,
+            negative_potential,
+            ehs: hand_strength * (1.0 - negative_potential) + (1.0 - hand_strength) * positive_potential,
+            ehs_squared: squares / runouts,
+        })
+    }
+
     fn update_frequencies(&self, hand: &[Card], frequencies: &mut HashMap<Hand, u8>) {
-        let best_hand = get_best_hand(&hand);
+        let best_hand = get_best_hand(hand);
         frequencies.entry(best_hand).and_modify(|counter| *counter += 1).or_insert(1);
     }
 
     fn update_frequencies_with_unused_cards(&self, hand: &[Card], unused_cards: &[Card], frequencies: &mut HashMap<Hand, u8>) {
-        let remaining_length = 5 - hand.len();
-        for final_cards in unused_cards.to_vec().into_iter().combinations(remaining_length) {
-            let mut this_hand = hand.to_vec();
-            this_hand.extend(final_cards);
-            self.update_frequencies(&this_hand, frequencies);
-        }
+        for_each_five_card_hand(hand, unused_cards, |this_hand| self.update_frequencies(this_hand, frequencies));
     }
 
     fn update_frequencies_from_starting_cards(&self, starting_cards: &[Card], unused_cards: &[Card], max_unused_cards: usize, frequencies: &mut HashMap<Hand, u8>) {
@@ -179,16 +990,7 @@ impl Game {
     }
     
     fn get_unused_cards(&self, used_cards: &[Card]) -> Vec<Card> {
-        let mut unused_cards: Vec<Card> = Vec::new();
-        for suit in [Suit::Spades, Suit::Diamonds, Suit::Clubs, Suit::Hearts] {
-            for value in 2..=14 {
-                let current_card = Card::build(suit, value).unwrap();
-                if !used_cards.contains(&current_card) {
-                    unused_cards.push(current_card);
-                }
-            }
-        }
-        unused_cards
+        self.variant.get_deck_without(used_cards)
     }
 
     pub fn get_best_hand_frequenicies(&self) -> (HashMap<Hand, u8>, HashMap<Hand, u8>) {
@@ -200,7 +1002,7 @@ impl Game {
         let starting_cards = &self.hole;
         self.update_frequencies_from_starting_cards(starting_cards, &unused_cards, 2, &mut my_hand_frequencies);
         // 1 of hole: Only unused cards could be turn and flop
-        for hole_card in self.hole {
+        for hole_card in self.hole.iter().copied() {
             let starting_cards = &[hole_card];
             self.update_frequencies_from_starting_cards(starting_cards, &unused_cards, 2, &mut my_hand_frequencies);
         }
@@ -208,7 +1010,7 @@ impl Game {
         let starting_cards = &self.flop;
         self.update_frequencies_from_starting_cards(starting_cards, &unused_cards, 2, &mut their_hand_frequencies);
         // 2 of Flop: Unused cards could be 3 of turn, flop, or their 2 cards
-        for starting_cards in self.flop.to_vec().into_iter().combinations(2) {
+        for starting_cards in self.flop.iter().copied().combinations(2) {
             self.update_frequencies_from_starting_cards(&starting_cards, &unused_cards, 3, &mut their_hand_frequencies);
         }
         // 1 of Flop: Unused cards could be turn, flop, or their 2 cards
@@ -238,7 +1040,7 @@ impl Game {
         // Hole + Flop
         let mut hand = self.hole.to_vec();
         hand.extend(&self.flop);
-        let best_hand = get_best_hand(&mut hand);
+        let best_hand = get_best_hand(&hand);
         my_hand_frequencies.insert(best_hand, 1);
         // Hole + 2 of Flop
         let mut hand = self.hole.to_vec();
@@ -291,7 +1093,7 @@ impl Game {
             }
         }
         // 1 of Hole + Flop
-        for hole_card in self.hole {
+        for hole_card in self.hole.iter().copied() {
             let mut hand = vec![hole_card];
             hand.extend(&self.flop);
             if let Some(turn) = self.turn {
@@ -313,7 +1115,7 @@ impl Game {
             }
         }
         // 1 of Hole + 2 of Flop
-        for hole_card in self.hole {
+        for hole_card in self.hole.iter().copied() {
             let mut hand = vec![hole_card];
             if let Some(turn) = self.turn {
                 let mut turn_hand = hand.clone();
@@ -430,6 +1232,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::single_match)]
     fn too_low_value() {
         let value = 0;
         for suit in [Suit::Spades, Suit::Diamonds, Suit::Clubs, Suit::Hearts] {
@@ -441,6 +1244,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::single_match)]
     fn too_high_value() {
         let value = 100;
         for suit in [Suit::Spades, Suit::Diamonds, Suit::Clubs, Suit::Hearts] {
@@ -452,6 +1256,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_mut_passed)]
     fn test_get_best_hand() {
         assert_eq!(get_best_hand(&mut [
             Card { suit: Suit::Clubs, value: 10 },
@@ -529,7 +1334,7 @@ mod tests {
     fn test_get_used_and_unused_cards() {
         let hole = [Card { suit: Suit::Diamonds, value: 12 }, Card { suit: Suit::Clubs, value: 11 }];
         let flop = [Card { suit: Suit::Spades, value: 10 }, Card { suit: Suit::Hearts, value: 8 }, Card { suit: Suit::Clubs, value: 3 }];
-        let game = Game { hole, flop, turn: None, river: None };
+        let game = Game { variant: Variant::Holdem, hole: hole.to_vec(), flop, turn: None, river: None };
         let used_cards = game.get_used_cards();
         let unused_cards = game.get_unused_cards(&used_cards);
         assert_eq!(used_cards.len(), 5);
@@ -544,7 +1349,7 @@ mod tests {
         }
 
         let turn = Card { suit: Suit::Hearts, value: 4 };
-        let game = Game { hole, flop, turn: Some(turn), river: None };
+        let game = Game { variant: Variant::Holdem, hole: hole.to_vec(), flop, turn: Some(turn), river: None };
         let used_cards = game.get_used_cards();
         let unused_cards = game.get_unused_cards(&used_cards);
         assert_eq!(used_cards.len(), 6);
@@ -561,7 +1366,7 @@ mod tests {
         assert!(!unused_cards.contains(&turn));
 
         let river = Card { suit: Suit::Diamonds, value: 9 };
-        let game = Game { hole, flop, turn: Some(turn), river: Some(river) };
+        let game = Game { variant: Variant::Holdem, hole: hole.to_vec(), flop, turn: Some(turn), river: Some(river) };
         let used_cards = game.get_used_cards();
         let unused_cards = game.get_unused_cards(&used_cards);
         assert_eq!(used_cards.len(), 7);
@@ -579,4 +1384,209 @@ mod tests {
         assert!(used_cards.contains(&river));
         assert!(!unused_cards.contains(&river));
     }
+
+    #[test]
+    fn test_parse_and_display_card() {
+        assert_eq!("Ah".parse::<Card>(), Ok(Card { suit: Suit::Hearts, value: 14 }));
+        assert_eq!("td".parse::<Card>(), Ok(Card { suit: Suit::Diamonds, value: 10 }));
+        assert_eq!("2c".parse::<Card>().unwrap().to_string(), "2c");
+        for card in ["", "A", "Ahh", "1h", "Ax"] {
+            if card.parse::<Card>().is_ok() {
+                panic!("{card} is not a valid card");
+            }
+        }
+        assert_eq!(parse_cards("As Kd").unwrap().len(), 2);
+        assert!(parse_cards("AsK").is_err());
+    }
+
+    #[test]
+    fn test_get_hand_rank() {
+        let rank = |cards: &str| get_hand_rank(&parse_cards(cards).unwrap());
+        assert_eq!(rank("AsKsQsJsTs9s2d").hand(), Hand::RoyalFlush);
+        assert_eq!(rank("As2s3s4s5s").hand(), Hand::StraightFlush(5));
+        assert_eq!(rank("9h9d9c9sKd2d").hand(), Hand::FourOfAKind(9));
+        assert_eq!(rank("9h9d9cKsKdKc2d").hand(), Hand::FullHouse(13, 9));
+        assert_eq!(rank("2h7h9hJhKh3h").hand(), Hand::Flush(13));
+        assert_eq!(rank("Ad2c3h4s5d9d").hand(), Hand::Straight(5));
+        assert_eq!(rank("6d2c3h4s5d9d").hand(), Hand::Straight(6));
+        assert_eq!(rank("7h7d7cAs2d").hand(), Hand::ThreeOfAKind(7));
+        assert_eq!(rank("7h7d3c3s2d2h").hand(), Hand::TwoPair(7, 3));
+        assert_eq!(rank("7h7d3c4s9d").hand(), Hand::Pair(7));
+        assert_eq!(rank("7h2d3c4s9d").hand(), Hand::HighCard(9));
+
+        // Kickers decide between hands of the same kind
+        assert!(rank("AhAdKc7s2d") > rank("AcAsQh7d2c"));
+        assert!(rank("7h7d3c3sAd") > rank("7c7s3h3dKd"));
+        assert_eq!(rank("AhKhQh9h2h").values(), vec![14, 13, 12, 9, 2]);
+        assert_eq!(rank("AhAd2c3s4d5h9c").hand(), Hand::Straight(5));
+        assert_eq!(rank("6h6dKcKs4d4hAc"), rank("KcKs6h6dAc"));
+    }
+
+    #[test]
+    fn test_build_game() {
+        let cards = parse_cards("AhKdQsJcTh9d").unwrap();
+        let game = Game::build([cards[0], cards[1]], [cards[2], cards[3], cards[4]], Some(cards[5]), None).unwrap();
+        assert_eq!(game.board(), cards[2..].to_vec());
+        assert!(Game::build([cards[0], cards[0]], [cards[2], cards[3], cards[4]], None, None).is_err());
+        assert!(Game::build([cards[0], cards[1]], [cards[2], cards[3], cards[4]], None, Some(cards[5])).is_err());
+    }
+
+    #[test]
+    fn test_get_equity() {
+        let cards = parse_cards("AhAdKsKc2h7d9c").unwrap();
+        let game = Game::build([cards[0], cards[1]], [cards[4], cards[5], cards[6]], None, None).unwrap();
+        let equity = game.get_equity(&[cards[2..4].to_vec()]).unwrap();
+        // Kings need one of the two remaining kings, which they hit in at most 87 of 990 runouts
+        assert!(equity[1].equity > 0.0 && equity[1].equity <= 87.0 / 990.0);
+        assert!((equity[0].equity + equity[1].equity - 1.0).abs() < 1e-9);
+
+        // The board plays for both hands
+        let board = parse_cards("AhKhQhJhTh").unwrap();
+        let equity = get_equity(&[parse_cards("2c3c").unwrap(), parse_cards("4d5d").unwrap()], &board, &[]).unwrap();
+        assert_eq!(equity[0], Equity { win: 0.0, tie: 1.0, equity: 0.5 });
+        assert!(get_equity(&[parse_cards("2c3c").unwrap()], &board, &[]).is_err());
+        assert!(get_equity(&[parse_cards("2c3c").unwrap(), parse_cards("2c5d").unwrap()], &[], &[]).is_err());
+    }
+
+    #[test]
+    fn test_get_equity_against_range() {
+        let cards = parse_cards("AhAd2h7d9cJs4s").unwrap();
+        let game = Game::build([cards[0], cards[1]], [cards[2], cards[3], cards[4]], Some(cards[5]), Some(cards[6])).unwrap();
+        let range = Range::parse("AA").unwrap();
+        assert_eq!(game.get_equity_against_range(&range).unwrap().equity, 0.5);
+        let range = Range::parse("AcAs").unwrap();
+        assert_eq!(game.get_equity_against_range(&range).unwrap().tie, 1.0);
+        let range = Range::parse("AhAd").unwrap();
+        assert!(game.get_equity_against_range(&range).is_err());
+    }
+
+    #[test]
+    fn test_get_omaha_hand_rank() {
+        let rank = |hole: &str, board: &str| get_omaha_hand_rank(&parse_cards(hole).unwrap(), &parse_cards(board).unwrap()).hand();
+        // Four of a suit on the board is no flush without two of the suit in hand
+        assert_eq!(rank("QhJs5d3c", "AhKhTh2h9c"), Hand::Straight(14));
+        assert_eq!(rank("QhJsTd8c", "AhKh7h2h9c"), Hand::HighCard(14));
+        assert_eq!(rank("Qh3hTd8c", "AhKh7h2h9c"), Hand::Flush(14));
+        // A single ace does not make the nut flush from three of a suit either
+        assert_eq!(rank("AhKsKdQc", "9h7h2h5c3d"), Hand::Pair(13));
+        // Trips on the board only fill up with a pair in hand
+        assert_eq!(rank("AsKd9c8h", "7s7d7c2h3d"), Hand::ThreeOfAKind(7));
+        assert_eq!(rank("AsAd9c8h", "7s7d7c2h3d"), Hand::FullHouse(7, 14));
+        // Four to a straight on the board needs two cards that fit
+        assert_eq!(rank("9sKdKc2h", "5s6d7c8hQd"), Hand::Pair(13));
+        assert_eq!(rank("9sTdKc2h", "5s6d7c8hQd"), Hand::Straight(10));
+        // Five and six card hands still use exactly two
+        assert_eq!(rank("AhQh3c4d5s6s", "KhJhTh2c2d"), Hand::RoyalFlush);
+        // Four to a royal on the board with one card of it in hand makes nothing
+        assert_eq!(rank("Ah3c4d5s6s", "KhQhJhTh2d"), Hand::HighCard(14));
+    }
+
+    #[test]
+    fn test_omaha_game() {
+        let cards = parse_cards("QhJsTd8cAhKh7h2h").unwrap();
+        let flop = [cards[4], cards[5], cards[6]];
+        assert!(Game::build_variant(Variant::Omaha, &cards[..2], flop, None, None).is_err());
+        assert!(Game::build_variant(Variant::Holdem, &cards[..4], flop, None, None).is_err());
+        let game = Game::build_variant(Variant::Omaha, &cards[..4], flop, Some(cards[7]), None).unwrap();
+        assert!(game.get_equity_against_range(&Range::parse("AA").unwrap()).is_err());
+
+        // A lone heart cannot take the pot from a hand holding two
+        let opponent = parse_cards("3h4h5s5d").unwrap();
+        let equity = game.get_equity(&[opponent]).unwrap();
+        assert_eq!(equity[0].equity + equity[1].equity, 1.0);
+        assert_eq!(equity[1].win, 1.0);
+        assert!(game.get_equity(&[parse_cards("3h4h").unwrap()]).is_err());
+    }
+
+    #[test]
+    fn test_omaha_hi_lo_split_equity() {
+        let build = |hole: &str, board: &str| {
+            let board = parse_cards(board).unwrap();
+            Game::build_variant(Variant::OmahaHiLo, &parse_cards(hole).unwrap(), [board[0], board[1], board[2]], board.get(3).copied(), board.get(4).copied()).unwrap()
+        };
+        // Both hold the wheel cards for low, but only the first has the high
+        let game = build("Ah2hKdKc", "3c4d8hKsQs");
+        let equity = game.get_split_equity(&[parse_cards("Ad2d9c9s").unwrap()]).unwrap();
+        assert_eq!(equity[0], SplitEquity { equity: 0.75, high: 0.5, low: 0.25, scoop: 0.0, quartered: 0.0 });
+        assert_eq!(equity[1], SplitEquity { equity: 0.25, high: 0.0, low: 0.25, scoop: 0.0, quartered: 1.0 });
+        assert_eq!(game.get_equity(&[parse_cards("Ad2d9c9s").unwrap()]).unwrap()[0].equity, 0.75);
+
+        // Best high and the only low scoops
+        let equity = game.get_split_equity(&[parse_cards("QdQc9c9s").unwrap()]).unwrap();
+        assert_eq!(equity[0].scoop, 1.0);
+        assert_eq!(equity[0].low, 0.5);
+
+        // Without a qualifying low the high hand takes everything
+        let game = build("Ah2hKdKc", "KsQsJd9h2c");
+        let equity = game.get_split_equity(&[parse_cards("Ad3d9c9s").unwrap()]).unwrap();
+        assert_eq!(equity[0], SplitEquity { equity: 1.0, high: 1.0, low: 0.0, scoop: 1.0, quartered: 0.0 });
+
+        // With cards to come, shares still add up to the whole pot
+        let game = build("Ah2hKdKc", "3c4d8h");
+        let equity = game.get_split_equity(&[parse_cards("Ad3d9c9s").unwrap()]).unwrap();
+        assert!((equity[0].equity + equity[1].equity - 1.0).abs() < 1e-9);
+        assert!(equity[0].low > equity[1].low);
+    }
+
+    #[test]
+    fn test_holdem_hi_lo_split_equity() {
+        let hands = [parse_cards("Ah2h").unwrap(), parse_cards("KdKc").unwrap()];
+        let board = parse_cards("3c4d8hKsQs").unwrap();
+        let equity = Variant::HoldemHiLo.get_split_equity(&hands, &board, &[]).unwrap();
+        assert_eq!(equity[0].low, 0.5);
+        assert_eq!(equity[1].high, 0.5);
+        assert_eq!(Variant::Holdem.get_split_equity(&hands, &board, &[]).unwrap()[1].scoop, 1.0);
+    }
+
+    #[test]
+    fn test_crazy_pineapple_discards() {
+        let cards = parse_cards("AhAdKsKhKd2c3s2s").unwrap();
+        let (hole, flop) = (&cards[..3], [cards[3], cards[4], cards[5]]);
+        let game = Game::build_variant(Variant::CrazyPineapple, hole, flop, None, None).unwrap();
+        let opponents = [parse_cards("QcQs").unwrap()];
+        assert!(game.get_equity(&opponents).is_err());
+
+        // Keeping the king for trips beats keeping the aces
+        let discards = game.get_discard_equities(&opponents).unwrap();
+        assert_eq!(discards.len(), 3);
+        let best = game.get_best_discard(&opponents).unwrap();
+        assert_eq!(best.discard.value(), 14);
+        assert!(best.equities[0].equity > discards[2].equities[0].equity);
+        let chosen = game.get_equity_with_discard(hole[2], &opponents).unwrap();
+        assert_eq!(chosen, discards[2].equities);
+        assert!(game.get_equity_with_discard(cards[7], &opponents).is_err());
+
+        assert!(Game::build_variant(Variant::CrazyPineapple, hole, flop, Some(cards[6]), None).is_err());
+        let board = flop.to_vec();
+        assert!(Variant::Pineapple.get_discard_equities(hole, &opponents, &board, &[]).is_err());
+        assert!(Variant::Holdem.get_discard_equities(hole, &opponents, &board, &[]).is_err());
+        assert!(Variant::CrazyPineapple.get_discard_equities(hole, &opponents, &board, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_hand_strength() {
+        // On the river nothing can change, so the strength is the share of hands beaten
+        let cards = parse_cards("AhAdAsKd9c8h2s").unwrap();
+        let game = Game::build([cards[0], cards[1]], [cards[2], cards[3], cards[4]], Some(cards[5]), Some(cards[6])).unwrap();
+        let river = game.get_hand_strength().unwrap();
+        assert_eq!(river.hand_strength, 1.0);
+        assert_eq!((river.positive_potential, river.negative_potential), (0.0, 0.0));
+        assert_eq!(river.ehs, river.hand_strength);
+        assert!((river.ehs_squared - river.hand_strength.powi(2)).abs() < 1e-12);
+
+        // A flush draw on the turn is behind now but often gets there
+        let cards = parse_cards("KhQhAh7h8s2cAcAd").unwrap();
+        let (flop, turn) = ([cards[2], cards[3], cards[4]], Some(cards[5]));
+        let strength = Game::build([cards[0], cards[1]], flop, turn, None).unwrap().get_hand_strength().unwrap();
+        assert!(strength.positive_potential > 0.15);
+        assert!(strength.ehs > strength.hand_strength);
+        assert!(strength.ehs_squared > strength.ehs.powi(2));
+
+        // A set on the same board can only get worse
+        let strength = Game::build([cards[6], cards[7]], flop, turn, None).unwrap().get_hand_strength().unwrap();
+        assert!(strength.negative_potential > 0.0 && strength.ehs < strength.hand_strength);
+
+        let omaha = Game::build_variant(Variant::Omaha, &parse_cards("KsKdQsQd").unwrap(), flop, None, None).unwrap();
+        assert!(omaha.get_hand_strength().is_err());
+    }
 }
@@ -0,0 +1,652 @@
+//! Counterfactual Regret Minimization for two-player zero-sum games with hidden information.
+//! Any game described by `TwoPlayerGame` can be solved with vanilla CFR, CFR+, or Monte Carlo
+//! CFR with external sampling, and the average strategy measured by its exploitability. Kuhn
+//! and Leduc poker are built in, and Hold'em games plug in by grouping hands with a
+//! `CardAbstraction`, as `BucketedRiver` does.
+
+use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::cards::{Card, Variant};
+use crate::range::Range;
+
+/// A point in a game tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    /// The end of the game, with what the first player wins, or loses as a negative number
+    Terminal(f64),
+    /// A random event with the probability of each outcome
+    Chance(Vec<f64>),
+    /// A choice between `actions` actions by `player`, who cannot tell this node apart from
+    /// others in the same information set
+    Decision { player: usize, info_set: String, actions: usize },
+}
+
+/// A two-player zero-sum game. Children of chance and decision nodes are numbered from zero.
+pub trait TwoPlayerGame {
+    type State: Clone;
+
+    fn root(&self) -> Self::State;
+
+    fn node(&self, state: &Self::State) -> Node;
+
+    fn next(&self, state: &Self::State, index: usize) -> Self::State;
+}
+
+/// Probabilities of each action in each information set. Information sets left out are played
+/// uniformly at random.
+pub type Profile = HashMap<String, Vec<f64>>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CfrVariant {
+    /// Every node visited every iteration
+    Vanilla,
+    /// Regrets floored at zero and later iterations weighted more in the average strategy,
+    /// which converges much faster
+    Plus,
+    /// Monte Carlo CFR sampling chance and the opponent's actions, for games too large to
+    /// traverse fully each iteration
+    ExternalSampling,
+}
+
+#[derive(Debug, Clone)]
+struct InfoSet {
+    regrets: Vec<f64>,
+    strategy_sum: Vec<f64>,
+    /// The strategy for the current iteration, which stays fixed while regrets build up
+    strategy: Vec<f64>,
+}
+
+fn normalise(weights: &[f64]) -> Vec<f64> {
+    let total: f64 = weights.iter().sum();
+    if total > 0.0 {
+        weights.iter().map(|weight| weight / total).collect()
+    } else {
+        vec![1.0 / weights.len() as f64; weights.len()]
+    }
+}
+
+impl InfoSet {
+    fn new(actions: usize) -> InfoSet {
+        InfoSet { regrets: vec![0.0; actions], strategy_sum: vec![0.0; actions], strategy: vec![1.0 / actions as f64; actions] }
+    }
+
+    /// Regret matching: each action in proportion to its positive regret
+    fn update_strategy(&mut self) {
+        self.strategy = normalise(&self.regrets.iter().map(|regret| regret.max(0.0)).collect::<Vec<f64>>());
+    }
+}
+
+/// The strategy `profile` plays in an information set
+pub fn get_action_probabilities(profile: &Profile, info_set: &str, actions: usize) -> Vec<f64> {
+    profile.get(info_set).cloned().unwrap_or_else(|| vec![1.0 / actions as f64; actions])
+}
+
+/// Picks an index with the given probabilities
+fn sample(rng: &mut StdRng, probabilities: &[f64]) -> usize {
+    let mut remaining = rng.random::<f64>();
+    for (index, probability) in probabilities.iter().enumerate() {
+        remaining -= probability;
+        if remaining < 0.0 {
+            return index;
+        }
+    }
+    probabilities.len() - 1
+}
+
+pub struct CfrSolver<G: TwoPlayerGame> {
+    game: G,
+    variant: CfrVariant,
+    info_sets: HashMap<String, InfoSet>,
+    iterations: u64,
+    rng: StdRng,
+}
+
+impl<G: TwoPlayerGame> CfrSolver<G> {
+    /// The seed is only used for sampling
+    pub fn new(game: G, variant: CfrVariant, seed: u64) -> CfrSolver<G> {
+        CfrSolver { game, variant, info_sets: HashMap::new(), iterations: 0, rng: StdRng::seed_from_u64(seed) }
+    }
+
+    pub fn game(&self) -> &G {
+        &self.game
+    }
+
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    /// Runs `iterations` more iterations, each updating both players in turn
+    pub fn train(&mut self, iterations: u64) {
+        let root = self.game.root();
+        for _ in 0..iterations {
+            self.iterations += 1;
+            for traverser in 0..2 {
+                if self.variant == CfrVariant::ExternalSampling {
+                    self.sample_traverse(&root, traverser);
+                    continue;
+                }
+                self.traverse(&root, traverser, [1.0, 1.0], 1.0);
+                for info_set in self.info_sets.values_mut() {
+                    if self.variant == CfrVariant::Plus {
+                        info_set.regrets.iter_mut().for_each(|regret| *regret = regret.max(0.0));
+                    }
+                    info_set.update_strategy();
+                }
+            }
+        }
+    }
+
+    fn strategy_at(&mut self, info_set: &str, actions: usize) -> Vec<f64> {
+        self.info_sets.entry(info_set.to_string()).or_insert_with(|| InfoSet::new(actions)).strategy.clone()
+    }
+
+    /// The value of `state` to `traverser`, updating the traverser's regrets on the way
+    fn traverse(&mut self, state: &G::State, traverser: usize, reach: [f64; 2], chance: f64) -> f64 {
+        match self.game.node(state) {
+            Node::Terminal(value) => if traverser == 0 { value } else { -value },
+            Node::Chance(probabilities) => probabilities.iter().enumerate()
+                .map(|(index, probability)| {
+                    let child = self.game.next(state, index);
+                    probability * self.traverse(&child, traverser, reach, chance * probability)
+                })
+                .sum(),
+            Node::Decision { player, info_set, actions } => {
+                let strategy = self.strategy_at(&info_set, actions);
+                let mut values = vec![0.0; actions];
+                let mut value = 0.0;
+                for action in 0..actions {
+                    let mut child_reach = reach;
+                    child_reach[player] *= strategy[action];
+                    let child = self.game.next(state, action);
+                    values[action] = self.traverse(&child, traverser, child_reach, chance);
+                    value += strategy[action] * values[action];
+                }
+                if player == traverser {
+                    let weight = if self.variant == CfrVariant::Plus { self.iterations as f64 } else { 1.0 };
+                    let counterfactual_reach = reach[1 - player] * chance;
+                    let entry = self.info_sets.get_mut(&info_set).expect("The strategy was just looked up");
+                    for action in 0..actions {
+                        entry.regrets[action] += counterfactual_reach * (values[action] - value);
+                        entry.strategy_sum[action] += weight * reach[player] * strategy[action];
+                    }
+                }
+                value
+            }
+        }
+    }
+
+    /// External sampling: one chance outcome and one opponent action are sampled at each of
+    /// their nodes, while every action of the traverser is explored
+    fn sample_traverse(&mut self, state: &G::State, traverser: usize) -> f64 {
+        match self.game.node(state) {
+            Node::Terminal(value) => if traverser == 0 { value } else { -value },
+            Node::Chance(probabilities) => {
+                let child = self.game.next(state, sample(&mut self.rng, &probabilities));
+                self.sample_traverse(&child, traverser)
+            }
+            Node::Decision { player, info_set, actions } => {
+                let strategy = self.strategy_at(&info_set, actions);
+                if player != traverser {
+                    let entry = self.info_sets.get_mut(&info_set).expect("The strategy was just looked up");
+                    for (sum, probability) in entry.strategy_sum.iter_mut().zip(&strategy) {
+                        *sum += probability;
+                    }
+                    let child = self.game.next(state, sample(&mut self.rng, &strategy));
+                    return self.sample_traverse(&child, traverser);
+                }
+                let values: Vec<f64> = (0..actions)
+                    .map(|action| {
+                        let child = self.game.next(state, action);
+                        self.sample_traverse(&child, traverser)
+                    })
+                    .collect();
+                let value: f64 = values.iter().zip(&strategy).map(|(value, probability)| value * probability).sum();
+                let entry = self.info_sets.get_mut(&info_set).expect("The strategy was just looked up");
+                for (regret, action_value) in entry.regrets.iter_mut().zip(&values) {
+                    *regret += action_value - value;
+                }
+                entry.update_strategy();
+                value
+            }
+        }
+    }
+
+    /// The average strategy over all iterations, which is what converges to an equilibrium
+    pub fn get_average_strategy(&self) -> Profile {
+        self.info_sets.iter().map(|(key, info_set)| (key.clone(), normalise(&info_set.strategy_sum))).collect()
+    }
+
+    pub fn get_exploitability(&self) -> f64 {
+        get_exploitability(&self.game, &self.get_average_strategy())
+    }
+}
+
+/// What the first player wins on average from `state` when both players follow `profile`
+pub fn get_state_value<G: TwoPlayerGame>(game: &G, profile: &Profile, state: &G::State) -> f64 {
+    match game.node(state) {
+        Node::Terminal(value) => value,
+        Node::Chance(probabilities) => probabilities.iter().enumerate()
+            .map(|(index, probability)| probability * get_state_value(game, profile, &game.next(state, index)))
+            .sum(),
+        Node::Decision { info_set, actions, .. } => get_action_probabilities(profile, &info_set, actions).iter().enumerate()
+            .map(|(action, probability)| probability * get_state_value(game, profile, &game.next(state, action)))
+            .sum(),
+    }
+}
+
+/// What the first player wins on average when both players follow `profile`
+pub fn get_expected_value<G: TwoPlayerGame>(game: &G, profile: &Profile) -> f64 {
+    get_state_value(game, profile, &game.root())
+}
+
+/// Finds the best response of one player to the other's fixed strategy. The best action in an
+/// information set weighs each node in it by how likely chance and the opponent are to reach it.
+struct BestResponse<'a, G: TwoPlayerGame> {
+    game: &'a G,
+    profile: &'a Profile,
+    player: usize,
+    nodes: HashMap<String, Vec<(G::State, f64)>>,
+    actions: HashMap<String, usize>,
+}
+
+impl<G: TwoPlayerGame> BestResponse<'_, G> {
+    fn collect(&mut self, state: &G::State, reach: f64) {
+        match self.game.node(state) {
+            Node::Terminal(_) => (),
+            Node::Chance(probabilities) => {
+                for (index, probability) in probabilities.iter().enumerate() {
+                    self.collect(&self.game.next(state, index), reach * probability);
+                }
+            }
+            Node::Decision { player, info_set, actions } => {
+                let probabilities = if player == self.player {
+                    self.nodes.entry(info_set).or_default().push((state.clone(), reach));
+                    vec![1.0; actions]
+                } else {
+                    get_action_probabilities(self.profile, &info_set, actions)
+                };
+                for (action, probability) in probabilities.iter().enumerate() {
+                    self.collect(&self.game.next(state, action), reach * probability);
+                }
+            }
+        }
+    }
+
+    fn best_action(&mut self, info_set: &str, actions: usize) -> usize {
+        if let Some(&action) = self.actions.get(info_set) {
+            return action;
+        }
+        let nodes = self.nodes.get(info_set).cloned().unwrap_or_default();
+        let values: Vec<f64> = (0..actions)
+            .map(|action| nodes.iter().map(|(state, reach)| reach * self.value(&self.game.next(state, action))).sum())
+            .collect();
+        let best = (0..actions).max_by(|a, b| values[*a].total_cmp(&values[*b])).unwrap_or(0);
+        self.actions.insert(info_set.to_string(), best);
+        best
+    }
+
+    fn value(&mut self, state: &G::State) -> f64 {
+        match self.game.node(state) {
+            Node::Terminal(value) => if self.player == 0 { value } else { -value },
+            Node::Chance(probabilities) => probabilities.iter().enumerate()
+                .map(|(index, probability)| probability * self.value(&self.game.next(state, index)))
+                .sum(),
+            Node::Decision { player, info_set, actions } if player == self.player => {
+                let action = self.best_action(&info_set, actions);
+                self.value(&self.game.next(state, action))
+            }
+            Node::Decision { info_set, actions, .. } => get_action_probabilities(self.profile, &info_set, actions).iter().enumerate()
+                .map(|(action, probability)| probability * self.value(&self.game.next(state, action)))
+                .sum(),
+        }
+    }
+}
+
+/// What `player` wins on average by playing the best response to the other player's strategy
+/// in `profile`
+pub fn get_best_response_value<G: TwoPlayerGame>(game: &G, profile: &Profile, player: usize) -> f64 {
+    let mut best_response = BestResponse { game, profile, player, nodes: HashMap::new(), actions: HashMap::new() };
+    let root = game.root();
+    best_response.collect(&root, 1.0);
+    best_response.value(&root)
+}
+
+/// How much a best response gains against `profile`, averaged over the two players. This is
+/// zero exactly for an equilibrium.
+pub fn get_exploitability<G: TwoPlayerGame>(game: &G, profile: &Profile) -> f64 {
+    (get_best_response_value(game, profile, 0) + get_best_response_value(game, profile, 1)) / 2.0
+}
+
+/// Groups Hold'em hands that a solver treats as the same, keeping an abstracted game small
+/// enough to solve. Buckets become part of the information set keys.
+pub trait CardAbstraction {
+    /// Fails when the abstraction cannot place the hand, such as a board from another street
+    fn get_bucket(&self, hole: &[Card], board: &[Card]) -> Result<usize, &'static str>;
+}
+
+/// A closure groups every hand it is given
+impl<F: Fn(&[Card], &[Card]) -> usize> CardAbstraction for F {
+    fn get_bucket(&self, hole: &[Card], board: &[Card]) -> Result<usize, &'static str> {
+        Ok(self(hole, board))
+    }
+}
+
+/// What the first player wins at a Hold'em showdown on a complete `board` when each player has
+/// put in `stake`
+pub fn get_showdown_utility(holes: [&[Card]; 2], board: &[Card], stake: f64) -> f64 {
+    let [first, second] = holes.map(|hole| Variant::Holdem.get_hand_rank(hole, board));
+    match first.cmp(&second) {
+        std::cmp::Ordering::Greater => stake,
+        std::cmp::Ordering::Less => -stake,
+        std::cmp::Ordering::Equal => 0.0,
+    }
+}
+
+const CARD_NAMES: [char; 3] = ['J', 'Q', 'K'];
+
+/// Kuhn poker: three cards, one each, an ante of one, and a single bet of one
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Kuhn;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KuhnState {
+    cards: Option<[usize; 2]>,
+    /// 'p' to pass, check or fold, and 'b' to bet or call
+    history: String,
+}
+
+const KUHN_DEALS: [[usize; 2]; 6] = [[0, 1], [0, 2], [1, 0], [1, 2], [2, 0], [2, 1]];
+
+impl TwoPlayerGame for Kuhn {
+    type State = KuhnState;
+
+    fn root(&self) -> KuhnState {
+        KuhnState { cards: None, history: String::new() }
+    }
+
+    fn node(&self, state: &KuhnState) -> Node {
+        let Some(cards) = state.cards else {
+            return Node::Chance(vec![1.0 / 6.0; 6]);
+        };
+        let showdown = |stake: f64| if cards[0] > cards[1] { stake } else { -stake };
+        match state.history.as_str() {
+            "pp" => Node::Terminal(showdown(1.0)),
+            "bb" | "pbb" => Node::Terminal(showdown(2.0)),
+            "bp" => Node::Terminal(1.0),
+            "pbp" => Node::Terminal(-1.0),
+            history => {
+                let player = history.len() % 2;
+                Node::Decision { player, info_set: format!("{}{}", CARD_NAMES[cards[player]], history), actions: 2 }
+            }
+        }
+    }
+
+    fn next(&self, state: &KuhnState, index: usize) -> KuhnState {
+        match state.cards {
+            None => KuhnState { cards: Some(KUHN_DEALS[index]), history: String::new() },
+            Some(_) => KuhnState { cards: state.cards, history: format!("{}{}", state.history, ['p', 'b'][index]) },
+        }
+    }
+}
+
+/// Leduc poker: a six card deck of two jacks, queens, and kings, one private card each and one
+/// public card, an ante of one, and two betting rounds with bets of two then four and at most
+/// two raises a round. A pair with the public card wins, and otherwise the higher card.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Leduc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeducState {
+    /// Both private cards then the public card, as indices into the deck
+    cards: Vec<usize>,
+    /// 'f' to fold, 'c' to check or call, and 'r' to bet or raise, for each round
+    rounds: [String; 2],
+}
+
+/// The outcome of one betting round's actions so far
+struct LeducRound {
+    /// Bets put in by each player
+    bets: [u32; 2],
+    raises: u32,
+    folded: Option<usize>,
+    is_over: bool,
+}
+
+impl LeducRound {
+    fn read(history: &str) -> LeducRound {
+        let mut round = LeducRound { bets: [0, 0], raises: 0, folded: None, is_over: false };
+        for (index, action) in history.chars().enumerate() {
+            let player = index % 2;
+            match action {
+                'r' => {
+                    round.bets[player] = round.bets[1 - player] + 1;
+                    round.raises += 1;
+                }
+                'c' => round.bets[player] = round.bets[1 - player],
+                _ => round.folded = Some(player),
+            }
+        }
+        round.is_over = round.folded.is_some() || (history.len() >= 2 && history.ends_with('c'));
+        round
+    }
+}
+
+impl Leduc {
+    fn rank(card: usize) -> usize {
+        card / 2
+    }
+
+    fn actions(state: &LeducState, round: &LeducRound) -> Vec<char> {
+        let player = state.rounds[state.current_round()].len() % 2;
+        let mut actions = Vec::new();
+        if round.bets[1 - player] > round.bets[player] {
+            actions.push('f');
+        }
+        actions.push('c');
+        if round.raises < 2 {
+            actions.push('r');
+        }
+        actions
+    }
+}
+
+impl LeducState {
+    fn current_round(&self) -> usize {
+        if self.cards.len() == 3 { 1 } else { 0 }
+    }
+
+    /// Chips each player has put in, including the ante
+    fn contributions(&self) -> [f64; 2] {
+        let mut contributions = [1.0, 1.0];
+        for (history, size) in self.rounds.iter().zip([2.0, 4.0]) {
+            let round = LeducRound::read(history);
+            for (contribution, bets) in contributions.iter_mut().zip(round.bets) {
+                *contribution += bets as f64 * size;
+            }
+        }
+        contributions
+    }
+}
+
+impl TwoPlayerGame for Leduc {
+    type State = LeducState;
+
+    fn root(&self) -> LeducState {
+        LeducState { cards: Vec::new(), rounds: [String::new(), String::new()] }
+    }
+
+    fn node(&self, state: &LeducState) -> Node {
+        let deal = || Node::Chance(vec![1.0 / (6 - state.cards.len()) as f64; 6 - state.cards.len()]);
+        if state.cards.len() < 2 {
+            return deal();
+        }
+        let current = state.current_round();
+        let round = LeducRound::read(&state.rounds[current]);
+        let contributions = state.contributions();
+        if let Some(folded) = round.folded {
+            return Node::Terminal(if folded == 0 { -contributions[0] } else { contributions[1] });
+        }
+        if round.is_over && current == 0 {
+            return deal();
+        }
+        if round.is_over {
+            let [first, second, public] = [0, 1, 2].map(|index| Leduc::rank(state.cards[index]));
+            let strength = |rank: usize| if rank == public { 3 + rank } else { rank };
+            let value = match strength(first).cmp(&strength(second)) {
+                std::cmp::Ordering::Greater => contributions[1],
+                std::cmp::Ordering::Less => -contributions[0],
+                std::cmp::Ordering::Equal => 0.0,
+            };
+            return Node::Terminal(value);
+        }
+        let player = state.rounds[current].len() % 2;
+        let public = state.cards.get(2).map_or(String::new(), |card| CARD_NAMES[Leduc::rank(*card)].to_string());
+        Node::Decision {
+            player,
+            info_set: format!("{}{}:{}|{}", CARD_NAMES[Leduc::rank(state.cards[player])], public, state.rounds[0], state.rounds[1]),
+            actions: Leduc::actions(state, &round).len(),
+        }
+    }
+
+    fn next(&self, state: &LeducState, index: usize) -> LeducState {
+        let mut next = state.clone();
+        let current = state.current_round();
+        let round = LeducRound::read(&state.rounds[current]);
+        if state.cards.len() < 2 || (round.is_over && round.folded.is_none()) {
+            let card = (0..6).filter(|card| !state.cards.contains(card)).nth(index).expect("The outcome is one of the cards left");
+            next.cards.push(card);
+        } else {
+            next.rounds[current].push(Leduc::actions(state, &round)[index]);
+        }
+        next
+    }
+}
+
+/// Hold'em on a complete board with Kuhn poker's betting: each player is dealt a hand from
+/// their range, antes one, and may check or bet two once. Information sets hold each hand's
+/// bucket from a `CardAbstraction` rather than its cards, so hands in a bucket play alike.
+pub struct BucketedRiver {
+    board: Vec<Card>,
+    /// Every pair of hole cards the two ranges can hold together, all equally likely
+    deals: Vec<[[Card; 2]; 2]>,
+    /// The bucket of each player's hand in each deal
+    buckets: Vec<[usize; 2]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BucketedRiverState {
+    deal: Option<usize>,
+    /// 'p' to pass, check or fold, and 'b' to bet or call
+    history: String,
+}
+
+impl BucketedRiver {
+    /// `ranges` are the first player's and then the second's
+    pub fn build(ranges: [&Range; 2], board: &[Card], abstraction: &impl CardAbstraction) -> Result<BucketedRiver, &'static str> {
+        if board.len() != 5 {
+            return Err("The board must be complete");
+        }
+        if board.iter().enumerate().any(|(index, card)| board[..index].contains(card)) {
+            return Err("The same card cannot be used twice");
+        }
+        let mut deals = Vec::new();
+        for first in ranges[0].combos_without(board) {
+            for second in ranges[1].combos_without(&[board, &first].concat()) {
+                deals.push([first, second]);
+            }
+        }
+        if deals.is_empty() {
+            return Err("The ranges have no hands that fit together on the board");
+        }
+        let buckets = deals.iter()
+            .map(|deal| Ok([abstraction.get_bucket(&deal[0], board)?, abstraction.get_bucket(&deal[1], board)?]))
+            .collect::<Result<Vec<[usize; 2]>, &'static str>>()?;
+        Ok(BucketedRiver { board: board.to_vec(), deals, buckets })
+    }
+}
+
+impl TwoPlayerGame for BucketedRiver {
+    type State = BucketedRiverState;
+
+    fn root(&self) -> BucketedRiverState {
+        BucketedRiverState { deal: None, history: String::new() }
+    }
+
+    fn node(&self, state: &BucketedRiverState) -> Node {
+        let Some(deal) = state.deal else {
+            return Node::Chance(vec![1.0 / self.deals.len() as f64; self.deals.len()]);
+        };
+        let holes = self.deals[deal].each_ref().map(|hole| hole.as_slice());
+        match state.history.as_str() {
+            "pp" => Node::Terminal(get_showdown_utility(holes, &self.board, 1.0)),
+            "bb" | "pbb" => Node::Terminal(get_showdown_utility(holes, &self.board, 3.0)),
+            "bp" => Node::Terminal(1.0),
+            "pbp" => Node::Terminal(-1.0),
+            history => {
+                let player = history.len() % 2;
+                Node::Decision { player, info_set: format!("{}:{history}", self.buckets[deal][player]), actions: 2 }
+            }
+        }
+    }
+
+    fn next(&self, state: &BucketedRiverState, index: usize) -> BucketedRiverState {
+        match state.deal {
+            None => BucketedRiverState { deal: Some(index), history: String::new() },
+            Some(_) => BucketedRiverState { deal: state.deal, history: format!("{}{}", state.history, ['p', 'b'][index]) },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+
+    #[test]
+    fn test_kuhn() {
+        for variant in [CfrVariant::Vanilla, CfrVariant::Plus] {
+            let mut solver = CfrSolver::new(Kuhn, variant, 0);
+            solver.train(2000);
+            assert!(solver.get_exploitability() < 0.005, "{variant:?}");
+            // The first player loses an eighteenth of a chip a hand at equilibrium
+            let value = get_expected_value(&Kuhn, &solver.get_average_strategy());
+            assert!((value + 1.0 / 18.0).abs() < 0.005, "{variant:?}");
+            // The second player always calls a bet with a king and always folds a jack
+            let strategy = solver.get_average_strategy();
+            assert!(strategy["Kb"][1] > 0.99 && strategy["Jb"][0] > 0.99);
+        }
+
+        let mut solver = CfrSolver::new(Kuhn, CfrVariant::ExternalSampling, 1);
+        solver.train(20000);
+        assert!(solver.get_exploitability() < 0.02);
+        assert_eq!(solver.iterations(), 20000);
+
+        // Always betting is far from an equilibrium
+        let always_bet: Profile = solver.get_average_strategy().keys().map(|key| (key.clone(), vec![0.0, 1.0])).collect();
+        assert!(get_exploitability(&Kuhn, &always_bet) > 0.3);
+    }
+
+    #[test]
+    fn test_leduc() {
+        let mut solver = CfrSolver::new(Leduc, CfrVariant::Plus, 0);
+        solver.train(100);
+        assert!(solver.get_exploitability() < 0.02);
+        // The first player loses about 0.086 a hand at equilibrium
+        let value = get_expected_value(&Leduc, &solver.get_average_strategy());
+        assert!((value + 0.0856).abs() < 0.01);
+        // Information sets cover every deal but not the suits
+        assert_eq!(solver.get_average_strategy().len(), 288);
+    }
+
+    #[test]
+    fn test_showdown_utility() {
+        let board = parse_cards("AhKd7c4s2s").unwrap();
+        let (aces, kings, other_kings) = (parse_cards("AsQc").unwrap(), parse_cards("KsQh").unwrap(), parse_cards("KcQd").unwrap());
+        assert_eq!(get_showdown_utility([&aces, &kings], &board, 5.0), 5.0);
+        assert_eq!(get_showdown_utility([&kings, &aces], &board, 5.0), -5.0);
+        assert_eq!(get_showdown_utility([&kings, &other_kings], &board, 5.0), 0.0);
+        let by_pair = |hole: &[Card], board: &[Card]| usize::from(board.iter().any(|card| hole.iter().any(|hole| hole.value() == card.value())));
+        assert_eq!(by_pair.get_bucket(&aces, &board), Ok(1));
+    }
+}
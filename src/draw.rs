@@ -0,0 +1,261 @@
+//! Drawing odds for games where players discard and draw replacements from the deck, such as
+//! five card draw and deuce-to-seven triple draw
+
+use std::collections::HashMap;
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use crate::cards::{for_each_five_card_hand, get_deck_without, get_hand_rank, Card, Hand};
+use crate::low::get_deuce_to_seven_hand_rank;
+
+/// Games where each player ends with five cards after drawing
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DrawGame {
+    /// The best high hand wins
+    FiveCardDraw,
+    /// The lowest hand wins, with aces high and straights and flushes counting against the hand
+    DeuceToSeven,
+}
+
+/// One way to play a hand in the draw
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawOption {
+    pub kept: Vec<Card>,
+    pub discarded: Vec<Card>,
+    /// Chance of finishing with a hand at least as good as the target
+    pub probability: f64,
+}
+
+fn check_cards(kept: &[Card], dead_cards: &[Card]) -> Result<Vec<Card>, &'static str> {
+    if kept.len() > 5 {
+        return Err("A hand cannot keep more than five cards");
+    }
+    let mut used_cards = kept.to_vec();
+    used_cards.extend(dead_cards);
+    if used_cards.iter().enumerate().any(|(index, card)| used_cards[..index].contains(card)) {
+        return Err("The same card cannot be used twice");
+    }
+    let unused_cards = get_deck_without(&used_cards);
+    if unused_cards.len() < 5 - kept.len() {
+        return Err("Not enough cards left in the deck to draw");
+    }
+    Ok(unused_cards)
+}
+
+fn check_target(target: &[Card]) -> Result<(), &'static str> {
+    if target.len() != 5 {
+        return Err("The target must be a five card hand");
+    }
+    Ok(())
+}
+
+impl DrawGame {
+    /// Strength of exactly five cards, where greater is better in this game
+    fn get_strength(&self, cards: &[Card]) -> u32 {
+        match self {
+            DrawGame::FiveCardDraw => get_hand_rank(cards).as_u32(),
+            DrawGame::DeuceToSeven => u32::MAX - get_deuce_to_seven_hand_rank(cards).as_u32(),
+        }
+    }
+
+    /// The hand exactly five cards make. A deuce-to-seven hand such as `HighCard(7)` is a seven
+    /// low, and A-2-3-4-5 is only ace high.
+    fn get_hand(&self, cards: &[Card]) -> Hand {
+        match self {
+            DrawGame::FiveCardDraw => get_hand_rank(cards).hand(),
+            DrawGame::DeuceToSeven => get_deuce_to_seven_hand_rank(cards).hand(),
+        }
+    }
+
+    /// Chance of each final hand after keeping `kept` and drawing back to five from the deck,
+    /// less `dead_cards` such as seen discards
+    pub fn get_draw_distribution(&self, kept: &[Card], dead_cards: &[Card]) -> Result<HashMap<Hand, f64>, &'static str> {
+        let unused_cards = check_cards(kept, dead_cards)?;
+        let mut frequencies: HashMap<Hand, usize> = HashMap::new();
+        let mut total = 0;
+        for_each_five_card_hand(kept, &unused_cards, |hand| {
+            *frequencies.entry(self.get_hand(hand)).or_insert(0) += 1;
+            total += 1;
+        });
+        Ok(frequencies.into_iter().map(|(hand, count)| (hand, count as f64 / total as f64)).collect())
+    }
+
+    /// Chance of finishing at least as well as the five `target` cards after one draw to `kept`
+    pub fn get_draw_probability(&self, kept: &[Card], dead_cards: &[Card], target: &[Card]) -> Result<f64, &'static str> {
+        check_target(target)?;
+        let unused_cards = check_cards(kept, dead_cards)?;
+        Ok(self.get_probability(kept, &unused_cards, self.get_strength(target)))
+    }
+
+    fn get_probability(&self, kept: &[Card], unused_cards: &[Card], target: u32) -> f64 {
+        let (mut hits, mut total) = (0, 0);
+        for_each_five_card_hand(kept, unused_cards, |hand| {
+            if self.get_strength(hand) >= target {
+                hits += 1;
+            }
+            total += 1;
+        });
+        hits as f64 / total as f64
+    }
+
+    /// Every way to draw from a five card hand, best chance of reaching `target` first. Options
+    /// that are equally good are ordered by drawing fewer cards.
+    pub fn get_discard_options(&self, hand: &[Card], dead_cards: &[Card], target: &[Card]) -> Result<Vec<DrawOption>, &'static str> {
+        if hand.len() != 5 {
+            return Err("A draw hand must have five cards");
+        }
+        check_target(target)?;
+        let unused_cards = check_cards(hand, dead_cards)?;
+        let target = self.get_strength(target);
+        let mut options: Vec<DrawOption> = (0..=5).rev()
+            .flat_map(|kept_length| hand.iter().copied().combinations(kept_length))
+            .map(|kept| {
+                let discarded = hand.iter().filter(|card| !kept.contains(card)).copied().collect();
+                // Discards go out of play, so they cannot be drawn back
+                let probability = self.get_probability(&kept, &unused_cards, target);
+                DrawOption { kept, discarded, probability }
+            })
+            .collect();
+        options.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+        Ok(options)
+    }
+
+    /// The draw from a five card hand with the best chance of reaching `target`
+    pub fn get_best_discard(&self, hand: &[Card], dead_cards: &[Card], target: &[Card]) -> Result<DrawOption, &'static str> {
+        Ok(self.get_discard_options(hand, dead_cards, target)?.remove(0))
+    }
+
+    /// The cards to keep for the next draw when a hand has not yet reached the target. Lowball
+    /// hands keep one card of each value no higher than the target's top card, breaking a made
+    /// straight or flush at its top. High hands keep the first kept cards and any new pairs.
+    fn get_next_kept(&self, kept: &[Card], hand: &[Card], target: &[Card]) -> Vec<Card> {
+        match self {
+            DrawGame::FiveCardDraw => hand.iter()
+                .filter(|card| kept.contains(card) || hand.iter().filter(|other| other.value() == card.value()).count() > 1)
+                .copied()
+                .collect(),
+            DrawGame::DeuceToSeven => {
+                let top = target.iter().map(|card| card.value()).max().unwrap();
+                let mut next_kept: Vec<Card> = hand.iter()
+                    .copied()
+                    .filter(|card| card.value() <= top)
+                    .sorted_by_key(|card| card.value())
+                    .dedup_by(|a, b| a.value() == b.value())
+                    .collect();
+                if next_kept.len() == 5 {
+                    next_kept.pop();
+                }
+                next_kept
+            }
+        }
+    }
+
+    /// Estimates the chance of reaching `target` within `draws` draws, starting by keeping
+    /// `kept`, from `trials` random deals. Once the target is made the hand stands pat, and
+    /// between draws it keeps the cards that work towards the target. Discards are never drawn
+    /// again. The same seed always gives the same estimate.
+    pub fn estimate_draws_probability(
+        &self,
+        kept: &[Card],
+        dead_cards: &[Card],
+        draws: usize,
+        target: &[Card],
+        trials: usize,
+        seed: u64,
+    ) -> Result<f64, &'static str> {
+        check_target(target)?;
+        let mut unused_cards = check_cards(kept, dead_cards)?;
+        if draws == 0 || trials == 0 {
+            return Err("At least one draw and one trial are needed");
+        }
+        if 5 - kept.len() + 5 * (draws - 1) > unused_cards.len() {
+            return Err("Not enough cards left in the deck to draw");
+        }
+        let strength = self.get_strength(target);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut hits = 0;
+        for _ in 0..trials {
+            unused_cards.shuffle(&mut rng);
+            let mut deck = unused_cards.iter().copied();
+            let mut hand = kept.to_vec();
+            for draw in 0..draws {
+                if draw > 0 {
+                    hand = self.get_next_kept(kept, &hand, target);
+                }
+                hand.extend(deck.by_ref().take(5 - hand.len()));
+                if self.get_strength(&hand) >= strength {
+                    hits += 1;
+                    break;
+                }
+            }
+        }
+        Ok(hits as f64 / trials as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+
+    fn cards(cards: &str) -> Vec<Card> {
+        parse_cards(cards).unwrap()
+    }
+
+    #[test]
+    fn test_get_draw_distribution() {
+        // Drawing one to an open ended straight draw
+        let distribution = DrawGame::FiveCardDraw.get_draw_distribution(&cards("9h8d7c6s"), &cards("Kh")).unwrap();
+        let straights: f64 = distribution.iter()
+            .filter(|(hand, _)| matches!(hand, Hand::Straight(_)))
+            .map(|(_, probability)| probability)
+            .sum();
+        assert!((straights - 8.0 / 47.0).abs() < 1e-9);
+        assert!((distribution.values().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        // Drawing one to 7-5-3-2 at deuce-to-seven
+        let distribution = DrawGame::DeuceToSeven.get_draw_distribution(&cards("7h5d3c2s"), &[]).unwrap();
+        assert!((distribution[&Hand::HighCard(7)] - 8.0 / 48.0).abs() < 1e-9);
+        assert!(DrawGame::DeuceToSeven.get_draw_distribution(&cards("7h5d3c2s"), &cards("7h")).is_err());
+    }
+
+    #[test]
+    fn test_get_draw_probability() {
+        let game = DrawGame::DeuceToSeven;
+        let target = cards("8h7d5c3s2h");
+        // Any four, six or eight makes an 8-7 low or better drawing to 7-5-3-2
+        let probability = game.get_draw_probability(&cards("7h5d3c2s"), &[], &target).unwrap();
+        assert!((probability - 12.0 / 48.0).abs() < 1e-9);
+        // Seen discards of those cards make the draw worse
+        let dead = game.get_draw_probability(&cards("7h5d3c2s"), &cards("4h4d6c"), &target).unwrap();
+        assert!((dead - 9.0 / 45.0).abs() < 1e-9);
+        assert!(game.get_draw_probability(&cards("7h5d3c2s"), &[], &cards("7h5d")).is_err());
+    }
+
+    #[test]
+    fn test_get_best_discard() {
+        // A made hand stands pat
+        let best = DrawGame::FiveCardDraw.get_best_discard(&cards("AhAdAcKsKh"), &[], &cards("AhAdAcKsKh")).unwrap();
+        assert_eq!(best.kept.len(), 5);
+        assert_eq!(best.probability, 1.0);
+
+        // Drawing to a seven low keeps the seven low cards and breaks the pair
+        let best = DrawGame::DeuceToSeven.get_best_discard(&cards("7h5d3c2s2h"), &[], &cards("7h5d4c3s2h")).unwrap();
+        assert_eq!(best.discarded.len(), 1);
+        assert_eq!(best.discarded[0].value(), 2);
+        assert!(best.probability > 0.0);
+    }
+
+    #[test]
+    fn test_estimate_draws_probability() {
+        let game = DrawGame::DeuceToSeven;
+        let (kept, target) = (cards("7h5d3c2s"), cards("8h7d5c3s2h"));
+        let one = game.estimate_draws_probability(&kept, &[], 1, &target, 4000, 3).unwrap();
+        assert!((one - 0.25).abs() < 0.03);
+        let three = game.estimate_draws_probability(&kept, &[], 3, &target, 4000, 3).unwrap();
+        assert!(three > one);
+        assert_eq!(three, game.estimate_draws_probability(&kept, &[], 3, &target, 4000, 3).unwrap());
+        assert!(game.estimate_draws_probability(&kept, &[], 0, &target, 4000, 3).is_err());
+    }
+}
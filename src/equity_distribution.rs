@@ -0,0 +1,181 @@
+//! Distributions of equity rather than a single number. A hand with 50% equity might be a coin
+//! flip against everything, or crushing half a range and drawing dead against the rest, and
+//! how well that equity is realised depends on which. The distribution can be taken over the
+//! opponent's combos, over the next board card, or over the combos of a whole range.
+
+use crate::cards::{get_deck_without, Card, Game, Variant};
+use crate::range::Range;
+
+/// Equities sorted from lowest to highest, each equally likely. Plotted in order against
+/// their share of the whole, they draw the equity distribution curve.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquityDistribution {
+    equities: Vec<f64>,
+}
+
+impl EquityDistribution {
+    pub fn build(mut equities: Vec<f64>) -> Result<EquityDistribution, &'static str> {
+        if equities.is_empty() {
+            return Err("A distribution needs at least one equity");
+        }
+        if equities.iter().any(|equity| !(0.0..=1.0).contains(equity)) {
+            return Err("Equities must be between 0 and 1");
+        }
+        equities.sort_by(f64::total_cmp);
+        Ok(EquityDistribution { equities })
+    }
+
+    pub fn equities(&self) -> &[f64] {
+        &self.equities
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.equities.iter().sum::<f64>() / self.equities.len() as f64
+    }
+
+    /// The equity below which a `fraction` of the distribution lies, interpolating between
+    /// neighbouring equities
+    pub fn get_quantile(&self, fraction: f64) -> Result<f64, &'static str> {
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err("The fraction must be between 0 and 1");
+        }
+        let position = fraction * (self.equities.len() - 1) as f64;
+        let (below, above) = (position.floor() as usize, position.ceil() as usize);
+        Ok(self.equities[below] + (position - below as f64) * (self.equities[above] - self.equities[below]))
+    }
+
+    /// The share of the distribution in each of `bins` equal ranges of equity, with an equity of
+    /// exactly 1 in the last
+    pub fn get_histogram(&self, bins: usize) -> Vec<f64> {
+        let mut histogram = vec![0.0; bins];
+        if bins == 0 {
+            return histogram;
+        }
+        for equity in &self.equities {
+            histogram[((equity * bins as f64) as usize).min(bins - 1)] += 1.0;
+        }
+        histogram.iter().map(|count| count / self.equities.len() as f64).collect()
+    }
+}
+
+fn check_holdem(game: &Game) -> Result<(), &'static str> {
+    if game.hole().len() != 2 || game.variant() != Variant::Holdem {
+        return Err("Equity distributions are only supported for Hold'em");
+    }
+    Ok(())
+}
+
+/// Equity of the game's hole cards against each combo in `range` not blocked by a known card,
+/// over every runout of the board
+pub fn get_equity_distribution(game: &Game, range: &Range) -> Result<EquityDistribution, &'static str> {
+    check_holdem(game)?;
+    let used = [game.hole(), &game.board()].concat();
+    let equities = range.combos_without(&used).iter()
+        .map(|combo| Ok(game.get_equity(&[combo.to_vec()])?[0].equity))
+        .collect::<Result<Vec<f64>, &'static str>>()?;
+    EquityDistribution::build(equities).map_err(|_| "Every combination in the range is blocked by a known card")
+}
+
+/// Equity of the game's hole cards against `range` after each card that could come next on the
+/// board. Cards that leave no combo in the range are left out.
+pub fn get_runout_equity_distribution(game: &Game, range: &Range) -> Result<EquityDistribution, &'static str> {
+    check_holdem(game)?;
+    let board = game.board();
+    if board.len() == 5 {
+        return Err("The board is already complete");
+    }
+    let hole = [game.hole()[0], game.hole()[1]];
+    let flop = [board[0], board[1], board[2]];
+    let mut equities = Vec::new();
+    for card in get_deck_without(&[game.hole(), &board].concat()) {
+        if range.combos_without(&[game.hole(), &board, &[card]].concat()).is_empty() {
+            continue;
+        }
+        let next = match board.get(3) {
+            Some(turn) => Game::build(hole, flop, Some(*turn), Some(card))?,
+            None => Game::build(hole, flop, Some(card), None)?,
+        };
+        equities.push(next.get_equity_against_range(range)?.equity);
+    }
+    EquityDistribution::build(equities).map_err(|_| "Every combination in the range is blocked by a known card")
+}
+
+/// Equity of each combo in `hero` against `villain` over every runout of the board. Combos
+/// blocked by the board, or that block every combo in `villain`, are left out.
+pub fn get_range_equity_distribution(
+    hero: &Range,
+    villain: &Range,
+    flop: [Card; 3],
+    turn: Option<Card>,
+    river: Option<Card>,
+) -> Result<EquityDistribution, &'static str> {
+    let board: Vec<Card> = flop.iter().copied().chain(turn).chain(river).collect();
+    let mut equities = Vec::new();
+    for combo in hero.combos_without(&board) {
+        if villain.combos_without(&[&board, &combo[..]].concat()).is_empty() {
+            continue;
+        }
+        let game = Game::build(combo, flop, turn, river)?;
+        equities.push(game.get_equity_against_range(villain)?.equity);
+    }
+    EquityDistribution::build(equities).map_err(|_| "No combination in the hero's range can face the villain's")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+
+    fn game(cards: &str) -> Game {
+        let cards = parse_cards(cards).unwrap();
+        Game::build([cards[0], cards[1]], [cards[2], cards[3], cards[4]], cards.get(5).copied(), cards.get(6).copied()).unwrap()
+    }
+
+    #[test]
+    fn test_distribution_statistics() {
+        let distribution = EquityDistribution::build(vec![1.0, 0.0, 0.5, 0.25, 0.75]).unwrap();
+        assert_eq!(distribution.equities(), [0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(distribution.mean(), 0.5);
+        assert_eq!(distribution.get_quantile(0.5).unwrap(), 0.5);
+        assert_eq!(distribution.get_quantile(0.125).unwrap(), 0.125);
+        assert_eq!(distribution.get_histogram(2), vec![0.4, 0.6]);
+        assert!(distribution.get_quantile(1.5).is_err());
+        assert!(EquityDistribution::build(vec![]).is_err());
+        assert!(EquityDistribution::build(vec![1.5]).is_err());
+    }
+
+    #[test]
+    fn test_equity_distribution_against_range() {
+        // Top pair crushes worse kings and is crushed by sets, with nothing in between
+        let game = game("AhKsKd7c2h9s");
+        let range = Range::parse("KQ, KJ, 77, 22").unwrap();
+        let distribution = get_equity_distribution(&game, &range).unwrap();
+        assert_eq!(distribution.equities().len(), 8 + 8 + 3 + 3);
+        let histogram = distribution.get_histogram(10);
+        assert_eq!(histogram[1..9].iter().sum::<f64>(), 0.0);
+        assert!((distribution.mean() - game.get_equity_against_range(&range).unwrap().equity).abs() < 1e-9);
+        assert!(get_equity_distribution(&game, &Range::parse("KsKd").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_runout_and_range_distributions() {
+        // A flush draw on the turn either gets there or misses
+        let draw = game("QhJhAh7h2c8s");
+        let runouts = get_runout_equity_distribution(&draw, &Range::parse("AA").unwrap()).unwrap();
+        assert_eq!(runouts.equities().len(), 46);
+        assert!(runouts.get_quantile(0.5).unwrap() < 0.1 && runouts.get_quantile(1.0).unwrap() > 0.9);
+        assert!(get_runout_equity_distribution(&game("AhKsKd7c2h9s3c"), &Range::parse("AA").unwrap()).is_err());
+
+        let cards = parse_cards("Ks7d2c4h").unwrap();
+        let distribution = get_range_equity_distribution(
+            &Range::parse("AA, 65s").unwrap(), &Range::parse("KQ").unwrap(), [cards[0], cards[1], cards[2]], Some(cards[3]), None,
+        ).unwrap();
+        assert_eq!(distribution.equities().len(), 6 + 4);
+        assert!(distribution.get_quantile(0.0).unwrap() > 0.1 && distribution.get_quantile(1.0).unwrap() > 0.75);
+        // Only fully blocked combos are skipped, and any other error is returned
+        let error = get_range_equity_distribution(
+            &Range::parse("AA").unwrap(), &Range::parse("KQ").unwrap(), [cards[0], cards[1], cards[2]], Some(cards[0]), None,
+        );
+        assert_eq!(error, Err("The same card cannot be used twice"));
+    }
+}
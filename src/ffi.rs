@@ -0,0 +1,347 @@
+//! C interface to the evaluator and equity calculations. The build script generates the header
+//! from this file into `OUT_DIR`; copy it over `include/poker_odds.h` after changing the interface.
+//!
+//! Cards are passed as strings such as "AhKd". Games and ranges are opaque handles that must be
+//! released with their matching `_free` function. Every fallible function returns a `PoStatus`
+//! and, on failure, stores a message that `po_last_error` returns.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use crate::cards::{get_hand_rank, parse_cards, Card, Game, Hand};
+use crate::range::Range;
+
+/// Result of every fallible call
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PoStatus {
+    Ok = 0,
+    /// A required pointer argument was null
+    NullPointer = 1,
+    /// A string was not valid UTF-8
+    InvalidString = 2,
+    /// A card could not be parsed
+    InvalidCard = 3,
+    /// The cards do not make a valid hand or game, such as a repeated card
+    InvalidGame = 4,
+    /// A range could not be parsed
+    InvalidRange = 5,
+    /// Equity could not be calculated, such as when the range is entirely blocked
+    InvalidEquity = 6,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PoHandCategory {
+    HighCard = 0,
+    Pair = 1,
+    TwoPair = 2,
+    ThreeOfAKind = 3,
+    Straight = 4,
+    Flush = 5,
+    FullHouse = 6,
+    FourOfAKind = 7,
+    StraightFlush = 8,
+    RoyalFlush = 9,
+    /// Only possible with wild cards
+    FiveOfAKind = 10,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PoHandRank {
+    pub category: PoHandCategory,
+    /// Kicker-correct strength; a higher value is a better hand
+    pub rank: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PoEquity {
+    pub win: f64,
+    pub tie: f64,
+    pub equity: f64,
+}
+
+/// Opaque handle to a game
+pub struct PoGame(Game);
+
+/// Opaque handle to a range
+pub struct PoRange(Range);
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// Every way a call can fail. Mapping errors to a `PoStatus` and message only happens in
+/// `report`, so each kind of failure always returns the same status.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum FfiError {
+    NullPointer,
+    InvalidString,
+    InvalidCard(&'static str),
+    InvalidGame(&'static str),
+    InvalidRange(&'static str),
+    InvalidEquity(&'static str),
+}
+
+impl FfiError {
+    fn status(self) -> PoStatus {
+        match self {
+            FfiError::NullPointer => PoStatus::NullPointer,
+            FfiError::InvalidString => PoStatus::InvalidString,
+            FfiError::InvalidCard(_) => PoStatus::InvalidCard,
+            FfiError::InvalidGame(_) => PoStatus::InvalidGame,
+            FfiError::InvalidRange(_) => PoStatus::InvalidRange,
+            FfiError::InvalidEquity(_) => PoStatus::InvalidEquity,
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            FfiError::NullPointer => "A required argument was null",
+            FfiError::InvalidString => "String is not valid UTF-8",
+            FfiError::InvalidCard(message)
+            | FfiError::InvalidGame(message)
+            | FfiError::InvalidRange(message)
+            | FfiError::InvalidEquity(message) => message,
+        }
+    }
+}
+
+/// Converts the result of a call to its status, storing the message of any error
+fn report(result: Result<(), FfiError>) -> PoStatus {
+    match result {
+        Ok(()) => PoStatus::Ok,
+        Err(error) => {
+            LAST_ERROR.with(|last_error| {
+                *last_error.borrow_mut() = CString::new(error.message()).unwrap_or_default();
+            });
+            error.status()
+        }
+    }
+}
+
+unsafe fn read_str<'a>(s: *const c_char) -> Result<&'a str, FfiError> {
+    if s.is_null() {
+        return Err(FfiError::NullPointer);
+    }
+    CStr::from_ptr(s).to_str().map_err(|_| FfiError::InvalidString)
+}
+
+unsafe fn read_cards(s: *const c_char) -> Result<Vec<Card>, FfiError> {
+    parse_cards(read_str(s)?).map_err(FfiError::InvalidCard)
+}
+
+/// Writes `value` through `out`, which must not be null
+unsafe fn write<T>(out: *mut T, value: T) -> Result<(), FfiError> {
+    if out.is_null() {
+        return Err(FfiError::NullPointer);
+    }
+    *out = value;
+    Ok(())
+}
+
+fn get_category(hand: Hand) -> PoHandCategory {
+    match hand {
+        Hand::HighCard(_) => PoHandCategory::HighCard,
+        Hand::Pair(_) => PoHandCategory::Pair,
+        Hand::TwoPair(_, _) => PoHandCategory::TwoPair,
+        Hand::ThreeOfAKind(_) => PoHandCategory::ThreeOfAKind,
+        Hand::Straight(_) => PoHandCategory::Straight,
+        Hand::Flush(_) => PoHandCategory::Flush,
+        Hand::FullHouse(_, _) => PoHandCategory::FullHouse,
+        Hand::FourOfAKind(_) => PoHandCategory::FourOfAKind,
+        Hand::StraightFlush(_) => PoHandCategory::StraightFlush,
+        Hand::RoyalFlush => PoHandCategory::RoyalFlush,
+        Hand::FiveOfAKind(_) => PoHandCategory::FiveOfAKind,
+    }
+}
+
+/// Message describing the last error on this thread. The string is owned by the library and is
+/// valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn po_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| last_error.borrow().as_ptr())
+}
+
+/// Ranks the best five-card hand among five to seven cards.
+///
+/// # Safety
+///
+/// `cards` must be a null-terminated string and `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn po_evaluate_hand(cards: *const c_char, out: *mut PoHandRank) -> PoStatus {
+    report((|| {
+        let cards = read_cards(cards)?;
+        if out.is_null() {
+            return Err(FfiError::NullPointer);
+        }
+        if !(5..=7).contains(&cards.len()) {
+            return Err(FfiError::InvalidGame("A hand must have between five and seven cards"));
+        }
+        if cards.iter().enumerate().any(|(index, card)| cards[..index].contains(card)) {
+            return Err(FfiError::InvalidGame("The same card cannot be used twice"));
+        }
+        let rank = get_hand_rank(&cards);
+        write(out, PoHandRank { category: get_category(rank.hand()), rank: rank.as_u32() })
+    })())
+}
+
+/// Creates a game from two hole cards and a board of three to five cards. On success `*out`
+/// holds a handle to release with `po_game_free`.
+///
+/// # Safety
+///
+/// `hole` and `board` must be null-terminated strings and `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn po_game_new(hole: *const c_char, board: *const c_char, out: *mut *mut PoGame) -> PoStatus {
+    report((|| {
+        let (hole, board) = (read_cards(hole)?, read_cards(board)?);
+        if out.is_null() {
+            return Err(FfiError::NullPointer);
+        }
+        if hole.len() != 2 || !(3..=5).contains(&board.len()) {
+            return Err(FfiError::InvalidGame("A game needs two hole cards and three to five board cards"));
+        }
+        let game = Game::build(
+            [hole[0], hole[1]],
+            [board[0], board[1], board[2]],
+            board.get(3).copied(),
+            board.get(4).copied(),
+        ).map_err(FfiError::InvalidGame)?;
+        write(out, Box::into_raw(Box::new(PoGame(game))))
+    })())
+}
+
+/// Releases a game. Passing null does nothing.
+///
+/// # Safety
+///
+/// `game` must be null or a handle from `po_game_new` that has not already been released.
+#[no_mangle]
+pub unsafe extern "C" fn po_game_free(game: *mut PoGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Parses a range such as "QQ+,AKs". On success `*out` holds a handle to release with
+/// `po_range_free`.
+///
+/// # Safety
+///
+/// `range` must be a null-terminated string and `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn po_range_parse(range: *const c_char, out: *mut *mut PoRange) -> PoStatus {
+    report((|| {
+        let range = read_str(range)?;
+        if out.is_null() {
+            return Err(FfiError::NullPointer);
+        }
+        let range = Range::parse(range).map_err(FfiError::InvalidRange)?;
+        write(out, Box::into_raw(Box::new(PoRange(range))))
+    })())
+}
+
+/// Number of combinations in a range, or 0 for a null handle.
+///
+/// # Safety
+///
+/// `range` must be null or a live handle from `po_range_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn po_range_len(range: *const PoRange) -> usize {
+    range.as_ref().map_or(0, |range| range.0.len())
+}
+
+/// Releases a range. Passing null does nothing.
+///
+/// # Safety
+///
+/// `range` must be null or a handle from `po_range_parse` that has not already been released.
+#[no_mangle]
+pub unsafe extern "C" fn po_range_free(range: *mut PoRange) {
+    if !range.is_null() {
+        drop(Box::from_raw(range));
+    }
+}
+
+/// Equity of the game's hole cards against an opponent holding any hand in `range`.
+///
+/// # Safety
+///
+/// `game` and `range` must be live handles and `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn po_game_equity(game: *const PoGame, range: *const PoRange, out: *mut PoEquity) -> PoStatus {
+    report((|| {
+        let (Some(game), Some(range)) = (game.as_ref(), range.as_ref()) else {
+            return Err(FfiError::NullPointer);
+        };
+        if out.is_null() {
+            return Err(FfiError::NullPointer);
+        }
+        let equity = game.0.get_equity_against_range(&range.0).map_err(FfiError::InvalidEquity)?;
+        write(out, PoEquity { win: equity.win, tie: equity.tie, equity: equity.equity })
+    })())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+    use super::*;
+
+    #[test]
+    fn test_header_is_current() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/poker_odds.h"));
+        assert_eq!(generated, include_str!("../include/poker_odds.h"), "include/poker_odds.h is out of date");
+    }
+
+    #[test]
+    fn test_evaluate_hand() {
+        let mut rank = PoHandRank { category: PoHandCategory::HighCard, rank: 0 };
+        let cards = CString::new("AhKhQhJhTh").unwrap();
+        assert_eq!(unsafe { po_evaluate_hand(cards.as_ptr(), &mut rank) }, PoStatus::Ok);
+        assert_eq!(rank.category, PoHandCategory::RoyalFlush);
+
+        let cards = CString::new("AhKhQhJh").unwrap();
+        assert_eq!(unsafe { po_evaluate_hand(cards.as_ptr(), &mut rank) }, PoStatus::InvalidGame);
+        let cards = CString::new("AhKhQhJhXh").unwrap();
+        assert_eq!(unsafe { po_evaluate_hand(cards.as_ptr(), &mut rank) }, PoStatus::InvalidCard);
+        assert_eq!(unsafe { po_evaluate_hand(ptr::null(), &mut rank) }, PoStatus::NullPointer);
+        let message = unsafe { CStr::from_ptr(po_last_error()) };
+        assert!(!message.to_bytes().is_empty());
+    }
+
+    #[test]
+    fn test_game_equity() {
+        let mut game = ptr::null_mut();
+        let mut range = ptr::null_mut();
+        let hole = CString::new("AhAd").unwrap();
+        let board = CString::new("2h7d9cJs4s").unwrap();
+        let opponent = CString::new("AA").unwrap();
+        unsafe {
+            assert_eq!(po_game_new(hole.as_ptr(), board.as_ptr(), &mut game), PoStatus::Ok);
+            assert_eq!(po_range_parse(opponent.as_ptr(), &mut range), PoStatus::Ok);
+            assert_eq!(po_range_len(range), 6);
+            let mut equity = PoEquity { win: 0.0, tie: 0.0, equity: 0.0 };
+            assert_eq!(po_game_equity(game, range, &mut equity), PoStatus::Ok);
+            assert_eq!(equity.equity, 0.5);
+            po_game_free(game);
+            po_range_free(range);
+
+            let mut game = ptr::null_mut();
+            assert_eq!(po_game_new(hole.as_ptr(), hole.as_ptr(), &mut game), PoStatus::InvalidGame);
+            assert!(game.is_null());
+
+            let blocked = CString::new("AhAd").unwrap();
+            let mut range = ptr::null_mut();
+            assert_eq!(po_game_new(hole.as_ptr(), board.as_ptr(), &mut game), PoStatus::Ok);
+            assert_eq!(po_range_parse(blocked.as_ptr(), &mut range), PoStatus::Ok);
+            let mut equity = PoEquity { win: 0.0, tie: 0.0, equity: 0.0 };
+            assert_eq!(po_game_equity(game, range, &mut equity), PoStatus::InvalidEquity);
+            assert_eq!(po_game_equity(game, range, ptr::null_mut()), PoStatus::NullPointer);
+            assert_eq!(CStr::from_ptr(po_last_error()).to_str().unwrap(), "A required argument was null");
+            po_game_free(game);
+            po_range_free(range);
+        }
+    }
+}
@@ -0,0 +1,549 @@
+//! Hand histories in the PokerStars text format, which several other sites also use. Each hand
+//! is read into a `HandRecord` that can be turned back into a `Game` for equity analysis.
+
+use std::fmt;
+use crate::cards::{parse_cards, Card, Game, Variant};
+
+/// Betting rounds of a flop game, in the order they are played
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Round {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+    Showdown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionKind {
+    PostSmallBlind(f64),
+    PostBigBlind(f64),
+    PostAnte(f64),
+    /// Any other forced bet, such as posting both blinds to enter
+    Post(f64),
+    Fold,
+    Check,
+    Call(f64),
+    Bet(f64),
+    /// Raises by the first amount to a total of the second
+    Raise(f64, f64),
+    /// An uncalled part of a bet handed back to the player
+    UncalledBetReturned(f64),
+    Show(Vec<Card>),
+    Muck,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Action {
+    pub player: String,
+    pub round: Round,
+    pub kind: ActionKind,
+    pub is_all_in: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player {
+    pub seat: usize,
+    pub name: String,
+    /// Chips at the start of the hand
+    pub stack: f64,
+    /// Hole cards, if they were dealt to the history's owner or shown
+    pub hole: Option<Vec<Card>>,
+}
+
+/// One hand as recorded in a hand history
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandRecord {
+    pub id: String,
+    /// The game and stakes, such as "Hold'em No Limit ($0.01/$0.02 USD)"
+    pub description: String,
+    pub table: String,
+    /// Seat holding the button
+    pub button: usize,
+    pub players: Vec<Player>,
+    pub flop: Option<[Card; 3]>,
+    pub turn: Option<Card>,
+    pub river: Option<Card>,
+    pub actions: Vec<Action>,
+    pub pot: f64,
+    pub rake: f64,
+    /// Each player collecting from a pot and the amount, once per pot collected
+    pub winners: Vec<(String, f64)>,
+    /// When the hand started, in ISO 8601 UTC, if known. Text histories give local times only.
+    pub start_date_utc: Option<String>,
+}
+
+/// A failure to read a hand history, with the one-based line it happened on
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn is_header(line: &str) -> bool {
+    line.starts_with("PokerStars ") && (line.contains(" Hand #") || line.contains(" Game #"))
+}
+
+/// Reads an amount such as "$1,250.50" or "1500"
+fn parse_amount(s: &str) -> Result<f64, &'static str> {
+    let s: String = s.trim().trim_start_matches(['$', '€', '£']).chars().filter(|c| *c != ',').collect();
+    s.parse().map_err(|_| "Invalid amount")
+}
+
+/// Reads the cards in the first pair of square brackets
+fn parse_bracketed_cards(s: &str) -> Result<Vec<Card>, &'static str> {
+    let open = s.find('[').ok_or("Expected cards in square brackets")?;
+    let close = s[open..].find(']').ok_or("Expected cards in square brackets")? + open;
+    parse_cards(&s[open + 1..close])
+}
+
+/// Reads the last pair of square brackets, which holds the newly dealt card on later streets
+fn parse_last_bracketed_cards(s: &str) -> Result<Vec<Card>, &'static str> {
+    let open = s.rfind('[').ok_or("Expected cards in square brackets")?;
+    parse_bracketed_cards(&s[open..])
+}
+
+fn parse_header(line: &str, record: &mut HandRecord) -> Result<(), &'static str> {
+    let start = line.find('#').ok_or("Expected a hand number")? + 1;
+    let end = line[start..].find(':').ok_or("Expected a colon after the hand number")? + start;
+    record.id = line[start..end].to_string();
+    let description = line[end + 1..].trim();
+    record.description = description.split(" - ").next().unwrap_or(description).to_string();
+    Ok(())
+}
+
+fn parse_table(line: &str, record: &mut HandRecord) -> Result<(), &'static str> {
+    let mut quoted = line.split('\'');
+    let (Some(_), Some(table)) = (quoted.next(), quoted.next()) else {
+        return Err("Expected the table name in quotes");
+    };
+    record.table = table.to_string();
+    let seat = line.split("Seat #").nth(1).ok_or("Expected the button's seat")?;
+    let seat = seat.split_whitespace().next().unwrap_or("");
+    record.button = seat.parse().map_err(|_| "Invalid button seat")?;
+    Ok(())
+}
+
+/// Reads "Seat 3: Name ($1.50 in chips)", which may be followed by notes such as "is sitting out"
+fn parse_seat(line: &str) -> Result<Player, &'static str> {
+    let rest = &line["Seat ".len()..];
+    let colon = rest.find(": ").ok_or("Expected a colon after the seat number")?;
+    let seat = rest[..colon].parse().map_err(|_| "Invalid seat number")?;
+    let rest = &rest[colon + 2..];
+    let chips = rest.find(" in chips").ok_or("Expected the player's chips")?;
+    let open = rest[..chips].rfind(" (").ok_or("Expected the player's chips in parentheses")?;
+    let stack = parse_amount(&rest[open + 2..chips])?;
+    Ok(Player { seat, name: rest[..open].to_string(), stack, hole: None })
+}
+
+/// Reads the amount at the start of `s`, ignoring anything after it
+fn parse_leading_amount(s: &str) -> Result<f64, &'static str> {
+    parse_amount(s.split_whitespace().next().unwrap_or(""))
+}
+
+/// Words that start a betting action, so a line starting with one that cannot be read is an error
+const ACTION_VERBS: [&str; 8] = ["posts ", "folds", "checks", "calls ", "bets ", "raises ", "shows ", "mucks "];
+
+/// Reads what follows "Name: ", or gives `None` for notes that do not affect the betting, such
+/// as "sits out", "is sitting out" or "is connected"
+fn parse_action(verb: &str) -> Result<Option<ActionKind>, &'static str> {
+    let verb = verb.trim_end_matches(" and is all-in");
+    let kind = if let Some(amount) = verb.strip_prefix("posts small blind ") {
+        ActionKind::PostSmallBlind(parse_leading_amount(amount)?)
+    } else if let Some(amount) = verb.strip_prefix("posts big blind ") {
+        ActionKind::PostBigBlind(parse_leading_amount(amount)?)
+    } else if let Some(amount) = verb.strip_prefix("posts the ante ") {
+        ActionKind::PostAnte(parse_leading_amount(amount)?)
+    } else if let Some(amount) = verb.strip_prefix("posts small & big blinds ") {
+        ActionKind::Post(parse_leading_amount(amount)?)
+    } else if verb == "folds" || verb.starts_with("folds [") {
+        ActionKind::Fold
+    } else if verb == "checks" {
+        ActionKind::Check
+    } else if let Some(amount) = verb.strip_prefix("calls ") {
+        ActionKind::Call(parse_leading_amount(amount)?)
+    } else if let Some(amount) = verb.strip_prefix("bets ") {
+        ActionKind::Bet(parse_leading_amount(amount)?)
+    } else if let Some(amounts) = verb.strip_prefix("raises ") {
+        let (by, to) = amounts.split_once(" to ").ok_or("Expected a raise to a total")?;
+        ActionKind::Raise(parse_amount(by)?, parse_leading_amount(to)?)
+    } else if verb.starts_with("shows ") {
+        ActionKind::Show(parse_bracketed_cards(verb)?)
+    } else if verb == "mucks hand" || verb == "doesn't show hand" {
+        ActionKind::Muck
+    } else if ACTION_VERBS.iter().any(|action| verb.starts_with(action)) {
+        return Err("Unrecognised action");
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(kind))
+}
+
+/// The seated player whose name starts `line` followed by `separator`, preferring the longest
+/// name when one player's name starts with another's
+fn find_player<'a>(players: &'a [Player], line: &str, separator: &str) -> Option<&'a Player> {
+    players.iter()
+        .filter(|player| line.strip_prefix(player.name.as_str()).is_some_and(|rest| rest.starts_with(separator)))
+        .max_by_key(|player| player.name.len())
+}
+
+struct Parser {
+    record: HandRecord,
+    round: Option<Round>,
+    is_summary: bool,
+}
+
+impl Parser {
+    fn new() -> Parser {
+        let record = HandRecord {
+            id: String::new(),
+            description: String::new(),
+            table: String::new(),
+            button: 0,
+            players: Vec::new(),
+            flop: None,
+            turn: None,
+            river: None,
+            actions: Vec::new(),
+            pot: 0.0,
+            rake: 0.0,
+            winners: Vec::new(),
+            start_date_utc: None,
+        };
+        Parser { record, round: None, is_summary: false }
+    }
+
+    fn finish(self) -> Result<HandRecord, &'static str> {
+        if self.record.players.is_empty() {
+            return Err("The hand has no seated players");
+        }
+        if self.round.is_none() {
+            return Err("The hand never deals hole cards");
+        }
+        Ok(self.record)
+    }
+
+    fn set_hole(&mut self, name: &str, hole: Vec<Card>) {
+        if let Some(player) = self.record.players.iter_mut().find(|player| player.name == name) {
+            player.hole = Some(hole);
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), &'static str> {
+        let record = &mut self.record;
+        if let Some(street) = line.strip_prefix("*** ") {
+            let (round, is_summary) = match street.split(" ***").next().unwrap_or("") {
+                "HOLE CARDS" => (Round::Preflop, false),
+                "FLOP" => {
+                    let flop = parse_bracketed_cards(street)?;
+                    record.flop = Some(flop.try_into().map_err(|_| "The flop must have three cards")?);
+                    (Round::Flop, false)
+                }
+                "TURN" => {
+                    record.turn = Some(*parse_last_bracketed_cards(street)?.first().ok_or("Expected the turn card")?);
+                    (Round::Turn, false)
+                }
+                "RIVER" => {
+                    record.river = Some(*parse_last_bracketed_cards(street)?.first().ok_or("Expected the river card")?);
+                    (Round::River, false)
+                }
+                "SHOW DOWN" => (Round::Showdown, false),
+                "SUMMARY" => (Round::Showdown, true),
+                _ => return Err("Unsupported street, such as a board run more than once"),
+            };
+            self.round = Some(round);
+            self.is_summary = is_summary;
+            return Ok(());
+        }
+        if self.is_summary {
+            if let Some(total) = line.strip_prefix("Total pot ") {
+                let mut parts = total.split(" | ");
+                record.pot = parse_leading_amount(parts.next().unwrap_or(""))?;
+                if let Some(rake) = parts.find_map(|part| part.strip_prefix("Rake ")) {
+                    record.rake = parse_leading_amount(rake)?;
+                }
+            }
+            return Ok(());
+        }
+        let Some(round) = self.round else {
+            if line.starts_with("Table '") {
+                return parse_table(line, record);
+            }
+            if line.starts_with("Seat ") {
+                record.players.push(parse_seat(line)?);
+                return Ok(());
+            }
+            // Blinds and antes are posted before the hole cards are dealt
+            return match find_player(&record.players, line, ": ") {
+                Some(player) => {
+                    let player = player.name.clone();
+                    if let Some(kind) = parse_action(&line[player.len() + 2..])? {
+                        let is_all_in = line.ends_with(" and is all-in");
+                        record.actions.push(Action { player, round: Round::Preflop, kind, is_all_in });
+                    }
+                    Ok(())
+                }
+                None => Ok(()),
+            };
+        };
+        if let Some(dealt) = line.strip_prefix("Dealt to ") {
+            let player = find_player(&record.players, dealt, " ").ok_or("Cards dealt to a player who is not seated")?;
+            let name = player.name.clone();
+            if dealt.contains('[') {
+                let hole = parse_last_bracketed_cards(dealt)?;
+                self.set_hole(&name, hole);
+            }
+            return Ok(());
+        }
+        if let Some(uncalled) = line.strip_prefix("Uncalled bet (") {
+            let (amount, player) = uncalled.split_once(") returned to ").ok_or("Expected who the uncalled bet returns to")?;
+            let kind = ActionKind::UncalledBetReturned(parse_amount(amount)?);
+            record.actions.push(Action { player: player.to_string(), round, kind, is_all_in: false });
+            return Ok(());
+        }
+        if let Some(player) = find_player(&record.players, line, " collected ") {
+            let name = player.name.clone();
+            let amount = parse_leading_amount(&line[name.len() + " collected ".len()..])?;
+            record.winners.push((name, amount));
+            return Ok(());
+        }
+        let Some(player) = find_player(&record.players, line, ": ") else {
+            // Chat, players joining or leaving, and other notes do not affect the hand
+            return Ok(());
+        };
+        let name = player.name.clone();
+        let Some(kind) = parse_action(&line[name.len() + 2..])? else {
+            return Ok(());
+        };
+        if let ActionKind::Show(hole) = &kind {
+            let hole = hole.clone();
+            self.set_hole(&name, hole);
+        }
+        let is_all_in = line.ends_with(" and is all-in");
+        self.record.actions.push(Action { player: name, round, kind, is_all_in });
+        Ok(())
+    }
+}
+
+/// Reads every hand in a hand history file. Hands start with their "PokerStars Hand #" header
+/// line; anything before the first header is an error.
+pub fn parse_hand_histories(text: &str) -> Result<Vec<HandRecord>, ParseError> {
+    let mut records = Vec::new();
+    let mut parser: Option<Parser> = None;
+    let mut header_line = 0;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        let fail = |message| ParseError { line: index + 1, message };
+        if line.is_empty() {
+            continue;
+        }
+        if is_header(line) {
+            if let Some(parser) = parser.take() {
+                records.push(parser.finish().map_err(|message| ParseError { line: header_line, message })?);
+            }
+            let mut next = Parser::new();
+            parse_header(line, &mut next.record).map_err(fail)?;
+            parser = Some(next);
+            header_line = index + 1;
+            continue;
+        }
+        let Some(parser) = parser.as_mut() else {
+            return Err(fail("Expected a hand to start with a PokerStars header"));
+        };
+        parser.parse_line(line).map_err(fail)?;
+    }
+    if let Some(parser) = parser {
+        records.push(parser.finish().map_err(|message| ParseError { line: header_line, message })?);
+    }
+    Ok(records)
+}
+
+/// Reads a single hand
+pub fn parse_hand_history(text: &str) -> Result<HandRecord, ParseError> {
+    let mut records = parse_hand_histories(text)?;
+    match records.len() {
+        1 => Ok(records.remove(0)),
+        0 => Err(ParseError { line: 1, message: "Expected a hand history" }),
+        _ => Err(ParseError { line: 1, message: "Expected only one hand" }),
+    }
+}
+
+impl HandRecord {
+    /// The variant of the game when it is one `Game` supports
+    pub fn variant(&self) -> Option<Variant> {
+        if self.description.contains("Omaha Hi/Lo") {
+            Some(Variant::OmahaHiLo)
+        } else if self.description.contains("Omaha") {
+            Some(Variant::Omaha)
+        } else if self.description.contains("Hold'em") {
+            Some(Variant::Holdem)
+        } else {
+            None
+        }
+    }
+
+    /// The board as it stood during `round`
+    pub fn board_at(&self, round: Round) -> Vec<Card> {
+        let mut board = Vec::new();
+        if round >= Round::Flop {
+            board.extend(self.flop.iter().flatten());
+        }
+        if round >= Round::Turn {
+            board.extend(self.turn);
+        }
+        if round >= Round::River {
+            board.extend(self.river);
+        }
+        board
+    }
+
+    pub fn player(&self, name: &str) -> Option<&Player> {
+        self.players.iter().find(|player| player.name == name)
+    }
+
+    /// Actions taken during `round`
+    pub fn actions_in(&self, round: Round) -> impl Iterator<Item = &Action> {
+        self.actions.iter().filter(move |action| action.round == round)
+    }
+
+    /// The game as `player` saw it during `round`, from the flop on
+    pub fn to_game(&self, player: &str, round: Round) -> Result<Game, &'static str> {
+        let variant = self.variant().ok_or("The hand's game is not supported")?;
+        let hole = self.player(player).ok_or("No such player")?.hole.as_ref().ok_or("The player's hole cards are unknown")?;
+        let flop = self.flop.ok_or("The hand ended before the flop")?;
+        let (turn, river) = match round {
+            Round::Preflop => return Err("A game starts from the flop"),
+            Round::Flop => (None, None),
+            Round::Turn => (Some(self.turn.ok_or("The hand ended before the turn")?), None),
+            Round::River | Round::Showdown => (
+                Some(self.turn.ok_or("The hand ended before the turn")?),
+                Some(self.river.ok_or("The hand ended before the river")?),
+            ),
+        };
+        Game::build_variant(variant, hole, flop, turn, river)
+    }
+
+    /// Hole cards known for every player other than `player`, such as those shown down
+    pub fn known_opponents(&self, player: &str) -> Vec<Vec<Card>> {
+        self.players.iter()
+            .filter(|other| other.name != player)
+            .filter_map(|other| other.hole.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HAND: &str = "\
+PokerStars Hand #123456789:  Hold'em No Limit ($0.01/$0.02 USD) - 2020/01/01 12:00:00 ET
+Table 'Alpha II' 6-max Seat #1 is the button
+Seat 1: Alice ($2.00 in chips)
+Seat 2: Bob Smith ($2.00 in chips)
+Seat 3: Carol ($1.50 in chips) is sitting out
+Alice: posts small blind $0.01
+Bob Smith: posts big blind $0.02
+*** HOLE CARDS ***
+Dealt to Alice [Ah Kd]
+Alice: raises $0.04 to $0.06
+Bob Smith: calls $0.04
+*** FLOP *** [2h 7d 9c]
+Bob Smith: checks
+Alice: bets $0.08
+Bob Smith: calls $0.08
+*** TURN *** [2h 7d 9c] [Js]
+Bob Smith: checks
+Alice said, \"nh\"
+Alice: checks
+*** RIVER *** [2h 7d 9c Js] [4s]
+Bob Smith: bets $0.20
+Alice: raises $1.66 to $1.86 and is all-in
+Bob Smith: calls $1.66
+*** SHOW DOWN ***
+Bob Smith: shows [Qs Qd] (a pair of Queens)
+Alice: shows [Ah Kd] (high card Ace)
+Bob Smith collected $3.96 from pot
+*** SUMMARY ***
+Total pot $4.00 | Rake $0.04
+Board [2h 7d 9c Js 4s]
+Seat 1: Alice (button) (small blind) showed [Ah Kd] and lost with high card Ace
+Seat 2: Bob Smith (big blind) showed [Qs Qd] and won ($3.96) with a pair of Queens
+";
+
+    #[test]
+    fn test_parse_hand_history() {
+        let record = parse_hand_history(HAND).unwrap();
+        assert_eq!(record.id, "123456789");
+        assert_eq!(record.description, "Hold'em No Limit ($0.01/$0.02 USD)");
+        assert_eq!(record.table, "Alpha II");
+        assert_eq!(record.button, 1);
+        assert_eq!(record.players.len(), 3);
+        assert_eq!(record.players[1].name, "Bob Smith");
+        assert_eq!(record.players[2].stack, 1.5);
+        assert_eq!(record.player("Alice").unwrap().hole, Some(parse_cards("AhKd").unwrap()));
+        assert_eq!(record.player("Bob Smith").unwrap().hole, Some(parse_cards("QsQd").unwrap()));
+        assert_eq!(record.board_at(Round::Turn), parse_cards("2h7d9cJs").unwrap());
+        assert_eq!(record.actions[0].kind, ActionKind::PostSmallBlind(0.01));
+        assert_eq!(record.actions_in(Round::Flop).count(), 3);
+        let shove = record.actions_in(Round::River).nth(1).unwrap();
+        assert_eq!(shove.kind, ActionKind::Raise(1.66, 1.86));
+        assert!(shove.is_all_in);
+        assert_eq!(record.pot, 4.0);
+        assert_eq!(record.rake, 0.04);
+        assert_eq!(record.winners, vec![("Bob Smith".to_string(), 3.96)]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse_hand_history(&HAND.replace("[2h 7d 9c]", "[2h 7d 9x]")).unwrap_err();
+        assert_eq!(error.line, 12);
+        assert_eq!(error.to_string(), "line 12: Card suit must be one of sdch");
+        let error = parse_hand_history(&HAND.replace("Bob Smith: checks\nAlice: bets", "Bob Smith: bets lots\nAlice: bets")).unwrap_err();
+        assert_eq!(error.line, 13);
+        assert_eq!(error.message, "Invalid amount");
+        assert_eq!(parse_hand_history("Seat 1: Alice ($2.00 in chips)").unwrap_err().line, 1);
+
+        let two_hands = format!("{HAND}\n\n{}", HAND.replace("123456789", "123456790"));
+        let records = parse_hand_histories(&two_hands).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].id, "123456790");
+        assert!(parse_hand_history(&two_hands).is_err());
+    }
+
+    #[test]
+    fn test_to_game() {
+        let record = parse_hand_history(HAND).unwrap();
+        assert!(record.to_game("Alice", Round::Preflop).is_err());
+        assert!(record.to_game("Carol", Round::Flop).is_err());
+        let game = record.to_game("Alice", Round::Turn).unwrap();
+        assert_eq!(game.board(), parse_cards("2h7d9cJs").unwrap());
+        let opponents = record.known_opponents("Alice");
+        assert_eq!(opponents, vec![parse_cards("QsQd").unwrap()]);
+        // Ace-king had six outs against the queens on the turn
+        let equity = game.get_equity(&opponents).unwrap();
+        assert!((equity[0].equity - 6.0 / 44.0).abs() < 1e-9);
+        let river = record.to_game("Bob Smith", Round::Showdown).unwrap();
+        assert_eq!(river.get_equity(&[parse_cards("AhKd").unwrap()]).unwrap()[0].win, 1.0);
+    }
+
+    #[test]
+    fn test_notes_are_skipped() {
+        // Real sessions mix notes about players in with the betting
+        let with_notes = HAND
+            .replace("Alice: posts small blind", "Carol: sits out\nAlice: posts small blind")
+            .replace("Bob Smith: calls $0.04\n", "Bob Smith: calls $0.04\nCarol: is sitting out\n")
+            .replace("Alice: bets $0.08\n", "Alice: bets $0.08\nBob Smith: is disconnected\nBob Smith: is connected\nBob Smith: has timed out\n")
+            .replace("Alice: checks\n", "Alice: checks\nCarol: leaves the table\n");
+        assert_ne!(with_notes, HAND);
+        assert_eq!(parse_hand_history(&with_notes).unwrap(), parse_hand_history(HAND).unwrap());
+    }
+}
@@ -0,0 +1,300 @@
+//! The Independent Chip Model, which turns tournament chip stacks into shares of the prize pool.
+//! Each player's chance of finishing first is their share of the chips, and the later places
+//! follow the same way among the players left (Malmuth-Harville). All-in decisions can then be
+//! judged by prize money as well as by chips, since chips won are worth less than chips lost.
+
+use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::cards::{Equity, Game};
+use crate::range::Range;
+
+/// Exact calculation tracks every set of finishers, so larger fields use `estimate_icm_equities`
+const MAX_EXACT_PLAYERS: usize = 20;
+
+fn check_prize_pool(stacks: &[f64], payouts: &[f64]) -> Result<(), &'static str> {
+    if stacks.iter().any(|stack| *stack < 0.0 || !stack.is_finite()) || payouts.iter().any(|payout| *payout < 0.0 || !payout.is_finite()) {
+        return Err("Stacks and payouts cannot be negative");
+    }
+    if !stacks.iter().any(|stack| *stack > 0.0) {
+        return Err("At least one player needs chips");
+    }
+    Ok(())
+}
+
+/// Players without chips finish behind everyone else, sharing those places equally
+fn share_busted_places(stacks: &[f64], payouts: &[f64], equities: &mut [f64]) {
+    let alive = stacks.iter().filter(|stack| **stack > 0.0).count();
+    let busted = stacks.len() - alive;
+    if busted == 0 {
+        return;
+    }
+    let share = (alive..stacks.len()).map(|place| payouts.get(place).copied().unwrap_or(0.0)).sum::<f64>() / busted as f64;
+    for (equity, stack) in equities.iter_mut().zip(stacks) {
+        if *stack == 0.0 {
+            *equity = share;
+        }
+    }
+}
+
+/// Expected payouts from the places after those already `placed`, a bit set of players
+fn get_harville_equities(stacks: &[f64], payouts: &[f64], placed: u64, memo: &mut HashMap<u64, Vec<f64>>) -> Vec<f64> {
+    let place = placed.count_ones() as usize;
+    let left: Vec<usize> = (0..stacks.len()).filter(|&player| placed & (1 << player) == 0 && stacks[player] > 0.0).collect();
+    if place >= payouts.len() || left.is_empty() {
+        return vec![0.0; stacks.len()];
+    }
+    if let Some(equities) = memo.get(&placed) {
+        return equities.clone();
+    }
+    let total: f64 = left.iter().map(|&player| stacks[player]).sum();
+    let mut equities = vec![0.0; stacks.len()];
+    for &player in &left {
+        let probability = stacks[player] / total;
+        let later = get_harville_equities(stacks, payouts, placed | (1 << player), memo);
+        for (equity, later) in equities.iter_mut().zip(later) {
+            *equity += probability * later;
+        }
+        equities[player] += probability * payouts[place];
+    }
+    memo.insert(placed, equities.clone());
+    equities
+}
+
+/// Each player's expected share of `payouts`, listed from first place down, under the
+/// Malmuth-Harville model
+pub fn get_icm_equities(stacks: &[f64], payouts: &[f64]) -> Result<Vec<f64>, &'static str> {
+    check_prize_pool(stacks, payouts)?;
+    if stacks.len() > MAX_EXACT_PLAYERS {
+        return Err("Too many players for an exact calculation; estimate it instead");
+    }
+    let mut equities = get_harville_equities(stacks, payouts, 0, &mut HashMap::new());
+    share_busted_places(stacks, payouts, &mut equities);
+    Ok(equities)
+}
+
+/// Estimates each player's expected share of `payouts` from `trials` random finishing orders
+/// under the same model, for fields too large to calculate exactly. The same seed always gives
+/// the same estimate.
+pub fn estimate_icm_equities(stacks: &[f64], payouts: &[f64], trials: usize, seed: u64) -> Result<Vec<f64>, &'static str> {
+    check_prize_pool(stacks, payouts)?;
+    if trials == 0 {
+        return Err("At least one trial is needed");
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let alive: Vec<usize> = (0..stacks.len()).filter(|&player| stacks[player] > 0.0).collect();
+    let mut equities = vec![0.0; stacks.len()];
+    let mut order: Vec<(f64, usize)> = Vec::with_capacity(alive.len());
+    for _ in 0..trials {
+        // Sorting by exponential race times finishes each player first with probability in
+        // proportion to their stack, and likewise for every later place
+        order.clear();
+        order.extend(alive.iter().map(|&player| (-(1.0 - rng.random::<f64>()).ln() / stacks[player], player)));
+        order.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        for ((_, player), payout) in order.iter().zip(payouts) {
+            equities[*player] += payout;
+        }
+    }
+    for equity in &mut equities {
+        *equity /= trials as f64;
+    }
+    share_busted_places(stacks, payouts, &mut equities);
+    Ok(equities)
+}
+
+/// How much better an action is than folding, in chips and in prize money
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DecisionEv {
+    pub chip_ev: f64,
+    pub dollar_ev: f64,
+}
+
+/// A tournament hand where the hero and a villain may get all their chips in heads-up
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllInSpot {
+    stacks: Vec<f64>,
+    posted: Vec<f64>,
+    payouts: Vec<f64>,
+    hero: usize,
+    villain: usize,
+}
+
+enum Outcome {
+    HeroWins,
+    VillainWins,
+    Split,
+    /// The hero folds, leaving the pot to the villain
+    HeroFolds,
+    /// The villain folds to the hero's all-in
+    VillainFolds,
+}
+
+impl AllInSpot {
+    /// `stacks` are each player's chips at the start of the hand, and `posted` what each has
+    /// already put in the pot, such as blinds and antes
+    pub fn build(stacks: &[f64], posted: &[f64], payouts: &[f64], hero: usize, villain: usize) -> Result<AllInSpot, &'static str> {
+        check_prize_pool(stacks, payouts)?;
+        if posted.len() != stacks.len() {
+            return Err("Give the chips posted by every player");
+        }
+        if posted.iter().zip(stacks).any(|(posted, stack)| *posted < 0.0 || posted > stack) {
+            return Err("A player cannot post more than their stack");
+        }
+        if hero == villain || hero >= stacks.len() || villain >= stacks.len() {
+            return Err("The hero and villain must be two different players");
+        }
+        Ok(AllInSpot { stacks: stacks.to_vec(), posted: posted.to_vec(), payouts: payouts.to_vec(), hero, villain })
+    }
+
+    fn get_stacks(&self, outcome: Outcome) -> Vec<f64> {
+        let mut stacks: Vec<f64> = self.stacks.iter().zip(&self.posted).map(|(stack, posted)| stack - posted).collect();
+        let dead: f64 = self.posted.iter().sum();
+        let (hero, villain) = (self.hero, self.villain);
+        let (winner, pot) = match outcome {
+            Outcome::HeroFolds => (Some(villain), dead),
+            Outcome::VillainFolds => (Some(hero), dead),
+            _ => {
+                // Both put in the smaller stack, including what they posted
+                let effective = self.stacks[hero].min(self.stacks[villain]);
+                stacks[hero] = self.stacks[hero] - effective;
+                stacks[villain] = self.stacks[villain] - effective;
+                let pot = dead - self.posted[hero] - self.posted[villain] + 2.0 * effective;
+                match outcome {
+                    Outcome::HeroWins => (Some(hero), pot),
+                    Outcome::VillainWins => (Some(villain), pot),
+                    _ => {
+                        stacks[hero] += pot / 2.0;
+                        stacks[villain] += pot / 2.0;
+                        (None, 0.0)
+                    }
+                }
+            }
+        };
+        if let Some(winner) = winner {
+            stacks[winner] += pot;
+        }
+        stacks
+    }
+
+    /// The hero's chips and prize equity after an outcome
+    fn get_value(&self, outcome: Outcome) -> (f64, f64) {
+        let stacks = self.get_stacks(outcome);
+        let equities = if stacks.len() > MAX_EXACT_PLAYERS {
+            estimate_icm_equities(&stacks, &self.payouts, 100_000, 0)
+        } else {
+            get_icm_equities(&stacks, &self.payouts)
+        };
+        (stacks[self.hero], equities.expect("Stacks after a hand stay valid")[self.hero])
+    }
+
+    /// The hero's chips and prize equity when the chips go in with `equity`
+    fn get_all_in_value(&self, equity: &Equity) -> (f64, f64) {
+        let loss = 1.0 - equity.win - equity.tie;
+        let outcomes = [(Outcome::HeroWins, equity.win), (Outcome::Split, equity.tie), (Outcome::VillainWins, loss)];
+        outcomes.into_iter().fold((0.0, 0.0), |(chips, dollars), (outcome, probability)| {
+            let (outcome_chips, outcome_dollars) = self.get_value(outcome);
+            (chips + probability * outcome_chips, dollars + probability * outcome_dollars)
+        })
+    }
+
+    /// Calling the villain's all-in with `equity`, compared to folding
+    pub fn get_call_ev(&self, equity: &Equity) -> DecisionEv {
+        let (chips, dollars) = self.get_all_in_value(equity);
+        let (fold_chips, fold_dollars) = self.get_value(Outcome::HeroFolds);
+        DecisionEv { chip_ev: chips - fold_chips, dollar_ev: dollars - fold_dollars }
+    }
+
+    /// Calling the villain's all-in after the flop against `range`, with the hero's cards and
+    /// the board in `game`
+    pub fn get_call_ev_against_range(&self, game: &Game, range: &Range) -> Result<DecisionEv, &'static str> {
+        Ok(self.get_call_ev(&game.get_equity_against_range(range)?))
+    }
+
+    /// Moving all-in, which the villain folds to with `fold_probability` and otherwise calls
+    /// with the hero having `equity`, compared to folding
+    pub fn get_shove_ev(&self, equity: &Equity, fold_probability: f64) -> DecisionEv {
+        let (called_chips, called_dollars) = self.get_all_in_value(equity);
+        let (steal_chips, steal_dollars) = self.get_value(Outcome::VillainFolds);
+        let (fold_chips, fold_dollars) = self.get_value(Outcome::HeroFolds);
+        DecisionEv {
+            chip_ev: fold_probability * steal_chips + (1.0 - fold_probability) * called_chips - fold_chips,
+            dollar_ev: fold_probability * steal_dollars + (1.0 - fold_probability) * called_dollars - fold_dollars,
+        }
+    }
+
+    /// The equity, ignoring ties, the hero needs to call by chips and by prize money. The gap
+    /// between them is the risk premium the payouts put on the call.
+    pub fn get_required_equity(&self) -> (f64, f64) {
+        let (win_chips, win_dollars) = self.get_value(Outcome::HeroWins);
+        let (lose_chips, lose_dollars) = self.get_value(Outcome::VillainWins);
+        let (fold_chips, fold_dollars) = self.get_value(Outcome::HeroFolds);
+        ((fold_chips - lose_chips) / (win_chips - lose_chips), (fold_dollars - lose_dollars) / (win_dollars - lose_dollars))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+
+    #[test]
+    fn test_get_icm_equities() {
+        let equities = get_icm_equities(&[5000.0, 3000.0, 2000.0], &[50.0, 30.0, 20.0]).unwrap();
+        // First half the time, second 0.3 * 5/7 + 0.2 * 5/8 of the time, and third otherwise
+        let second = 0.3 * 5.0 / 7.0 + 0.2 * 5.0 / 8.0;
+        assert!((equities[0] - (25.0 + 30.0 * second + 20.0 * (0.5 - second))).abs() < 1e-9);
+        assert!((equities.iter().sum::<f64>() - 100.0).abs() < 1e-9);
+
+        let equal = get_icm_equities(&[100.0; 4], &[60.0, 40.0]).unwrap();
+        assert!(equal.iter().all(|equity| (equity - 25.0).abs() < 1e-9));
+        // Busted players share the places after everyone left
+        assert_eq!(get_icm_equities(&[100.0, 0.0, 0.0], &[60.0, 30.0, 10.0]).unwrap(), vec![60.0, 20.0, 20.0]);
+        assert!(get_icm_equities(&[0.0, 0.0], &[1.0]).is_err());
+        assert!(get_icm_equities(&[1.0; 21], &[1.0]).is_err());
+    }
+
+    #[test]
+    fn test_estimate_icm_equities() {
+        let (stacks, payouts) = ([4000.0, 2500.0, 2000.0, 1000.0, 500.0], [50.0, 30.0, 20.0]);
+        let exact = get_icm_equities(&stacks, &payouts).unwrap();
+        let estimate = estimate_icm_equities(&stacks, &payouts, 50_000, 1).unwrap();
+        for (exact, estimate) in exact.iter().zip(&estimate) {
+            assert!((exact - estimate).abs() < 0.5, "{exact} {estimate}");
+        }
+        assert_eq!(estimate, estimate_icm_equities(&stacks, &payouts, 50_000, 1).unwrap());
+
+        // A field too large to calculate exactly
+        let stacks: Vec<f64> = (1..=100).map(|stack| stack as f64).collect();
+        let estimate = estimate_icm_equities(&stacks, &[50.0, 30.0, 20.0], 2000, 2).unwrap();
+        assert!((estimate.iter().sum::<f64>() - 100.0).abs() < 1e-6);
+        assert!(estimate[99] > estimate[0]);
+    }
+
+    #[test]
+    fn test_bubble_call() {
+        // On the bubble a coin flip that wins chips loses prize money
+        let spot = AllInSpot::build(&[4000.0, 3000.0, 1500.0, 1500.0], &[0.0; 4], &[50.0, 30.0, 20.0], 1, 0).unwrap();
+        let flip = Equity { win: 0.55, tie: 0.0, equity: 0.55 };
+        let call = spot.get_call_ev(&flip);
+        assert!((call.chip_ev - (0.55 * 6000.0 - 3000.0)).abs() < 1e-9);
+        assert!(call.dollar_ev < 0.0);
+        let (chips, dollars) = spot.get_required_equity();
+        assert!((chips - 0.5).abs() < 1e-9 && dollars > 0.55);
+
+        // Shoving picks up the blinds when the villain folds
+        let spot = AllInSpot::build(&[4000.0, 3000.0, 1500.0, 1500.0], &[100.0, 50.0, 0.0, 0.0], &[50.0, 30.0, 20.0], 1, 0).unwrap();
+        let shove = spot.get_shove_ev(&flip, 1.0);
+        assert!((shove.chip_ev - 150.0).abs() < 1e-9 && shove.dollar_ev > 0.0);
+        assert!(AllInSpot::build(&[100.0, 100.0], &[0.0, 0.0], &[1.0], 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_call_against_range() {
+        let spot = AllInSpot::build(&[2000.0, 2000.0, 1000.0], &[0.0; 3], &[65.0, 35.0], 0, 1).unwrap();
+        let cards = parse_cards("AhKhQhJd2c").unwrap();
+        let game = Game::build([cards[0], cards[1]], [cards[2], cards[3], cards[4]], None, None).unwrap();
+        let range = Range::parse("QQ, JJ, 22").unwrap();
+        let equity = game.get_equity_against_range(&range).unwrap();
+        assert_eq!(spot.get_call_ev_against_range(&game, &range).unwrap(), spot.get_call_ev(&equity));
+    }
+}
@@ -1 +1,24 @@
+pub mod abstraction;
+pub mod all_in;
+pub mod bots;
 pub mod cards;
+pub mod cfr;
+pub mod draw;
+pub mod equity_distribution;
+pub mod ffi;
+pub mod history;
+pub mod icm;
+pub mod low;
+pub mod ofc;
+pub mod ohh;
+pub mod postflop;
+pub mod pots;
+pub mod push_fold;
+pub mod range;
+pub mod short_deck;
+pub mod stud;
+pub mod table;
+pub mod three_card;
+pub mod wild;
+#[cfg(feature = "python")]
+pub mod python;
@@ -0,0 +1,283 @@
+use std::cmp::Ordering;
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use crate::cards::{count_deals, deal_from, for_each_deal, get_deck_without, get_hand_rank, split_between, Card, Equity, EquityTotals, HandRank, MAX_EXACT_DEALS};
+
+/// A low hand, where lower values are better. Compares so that the better low is greater, which
+/// matches `HandRank` and lets the best hand be found with `max`. Lows are only comparable with
+/// lows from the same evaluator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct LowHand(u32);
+
+impl LowHand {
+    /// `values` must be sorted from most to least significant
+    fn new(category: u32, values: &[u8]) -> LowHand {
+        let mut low = category;
+        for index in 0..5 {
+            low = (low << 4) | *values.get(index).unwrap_or(&0) as u32;
+        }
+        LowHand(low)
+    }
+
+    /// The deciding values of the low, most significant first. Ace-to-five lows count aces as 1
+    /// and deuce-to-seven lows count them as 14.
+    pub fn values(&self) -> Vec<u8> {
+        (0..5).rev().map(|index| ((self.0 >> (4 * index)) & 0xf) as u8).filter(|value| *value != 0).collect()
+    }
+}
+
+impl Ord for LowHand {
+    fn cmp(&self, other: &LowHand) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+impl PartialOrd for LowHand {
+    fn partial_cmp(&self, other: &LowHand) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn get_low_value(card: &Card) -> u8 {
+    if card.value() == 14 { 1 } else { card.value() }
+}
+
+/// The best eight-or-better low among any number of cards: five different values of eight or
+/// below, with aces low and straights and flushes ignored. `None` if no low qualifies.
+pub fn get_eight_or_better_low(cards: &[Card]) -> Option<LowHand> {
+    let values: Vec<u8> = cards.iter()
+        .map(get_low_value)
+        .filter(|value| *value <= 8)
+        .sorted()
+        .dedup()
+        .take(5)
+        .collect();
+    if values.len() < 5 {
+        return None;
+    }
+    Some(LowHand::new(0, &values.into_iter().rev().collect::<Vec<u8>>()))
+}
+
+/// Ranks exactly five cards for ace-to-five lowball: aces low, straights and flushes ignored,
+/// and pairs counting against the hand
+fn get_five_card_ace_to_five_low(cards: &[Card]) -> LowHand {
+    let mut counts = [0u8; 14];
+    for card in cards {
+        counts[get_low_value(card) as usize] += 1;
+    }
+    // Values grouped by how many times they appear, most often then highest first
+    let mut groups: Vec<(u8, u8)> = (1..=13u8).rev()
+        .filter(|value| counts[*value as usize] > 0)
+        .map(|value| (counts[value as usize], value))
+        .collect();
+    groups.sort_by_key(|group| std::cmp::Reverse(group.0));
+    let category = match (groups[0].0, groups.get(1).map(|group| group.0)) {
+        (4, _) => 5,
+        (3, Some(2)) => 4,
+        (3, _) => 3,
+        (2, Some(2)) => 2,
+        (2, _) => 1,
+        _ => 0,
+    };
+    LowHand::new(category, &groups.iter().map(|group| group.1).collect::<Vec<u8>>())
+}
+
+/// The best ace-to-five low, as played in Razz, among five or more cards
+pub fn get_ace_to_five_low(cards: &[Card]) -> LowHand {
+    cards.iter().copied().combinations(5).map(|hand| get_five_card_ace_to_five_low(&hand)).max().unwrap()
+}
+
+/// The usual high ranking of exactly five cards, except that aces are always high so A-2-3-4-5
+/// is only ace high. Deuce-to-seven lowball is the reverse of this ranking.
+pub fn get_deuce_to_seven_hand_rank(cards: &[Card]) -> HandRank {
+    let mut values: Vec<u8> = cards.iter().map(|card| card.value()).collect();
+    values.sort();
+    if values == [2, 3, 4, 5, 14] {
+        let is_flush = cards.iter().all(|card| card.suit() == cards[0].suit());
+        return HandRank::new(if is_flush { 5 } else { 0 }, &[14, 5, 4, 3, 2]);
+    }
+    get_hand_rank(cards)
+}
+
+fn get_five_card_deuce_to_seven_low(cards: &[Card]) -> LowHand {
+    LowHand(get_deuce_to_seven_hand_rank(cards).as_u32())
+}
+
+/// The best deuce-to-seven low among five or more cards
+pub fn get_deuce_to_seven_low(cards: &[Card]) -> LowHand {
+    cards.iter().copied().combinations(5).map(|hand| get_five_card_deuce_to_seven_low(&hand)).max().unwrap()
+}
+
+/// Checks the kept hands and returns the cards left in the deck with how many each hand draws
+fn get_draws(kept: &[Vec<Card>], dead_cards: &[Card]) -> Result<(Vec<Card>, Vec<usize>), &'static str> {
+    if kept.len() < 2 {
+        return Err("At least two hands are needed to calculate equity");
+    }
+    if kept.iter().any(|hand| hand.len() > 5) {
+        return Err("A hand cannot keep more than five cards");
+    }
+    let mut used_cards: Vec<Card> = kept.concat();
+    used_cards.extend(dead_cards);
+    if used_cards.iter().enumerate().any(|(index, card)| used_cards[..index].contains(card)) {
+        return Err("The same card cannot be used twice");
+    }
+    let unused_cards = get_deck_without(&used_cards);
+    let counts: Vec<usize> = kept.iter().map(|hand| 5 - hand.len()).collect();
+    if counts.iter().sum::<usize>() > unused_cards.len() {
+        return Err("Not enough cards left in the deck to draw");
+    }
+    Ok((unused_cards, counts))
+}
+
+fn get_deuce_to_seven_shares(kept: &[Vec<Card>], draws: &[Vec<Card>]) -> Vec<f64> {
+    let lows: Vec<LowHand> = kept.iter().zip(draws)
+        .map(|(hand, draw)| {
+            let mut cards = hand.clone();
+            cards.extend(draw);
+            get_deuce_to_seven_low(&cards)
+        })
+        .collect();
+    split_between(&lows)
+}
+
+/// Equity of deuce-to-seven single draw hands after the draw, where each player keeps some cards
+/// and draws back to five. `dead_cards` are cards known to be out of the deck, such as seen
+/// discards. Every draw from the remaining deck is enumerated, which is only allowed up to a
+/// million draws; estimate larger ones with `estimate_deuce_to_seven_equity`.
+pub fn get_deuce_to_seven_equity(kept: &[Vec<Card>], dead_cards: &[Card]) -> Result<Vec<Equity>, &'static str> {
+    let (unused_cards, counts) = get_draws(kept, dead_cards)?;
+    if count_deals(unused_cards.len(), &counts) > MAX_EXACT_DEALS {
+        return Err("Too many draws for an exact calculation; estimate it instead");
+    }
+    let mut totals = EquityTotals::new(kept.len());
+    for_each_deal(&unused_cards, &counts, &mut |draws| totals.add(&get_deuce_to_seven_shares(kept, draws)));
+    Ok(totals.into_equity())
+}
+
+/// Estimates deuce-to-seven equity after the draw from `trials` random draws. The same seed
+/// always gives the same estimate.
+pub fn estimate_deuce_to_seven_equity(
+    kept: &[Vec<Card>],
+    dead_cards: &[Card],
+    trials: usize,
+    seed: u64,
+) -> Result<Vec<Equity>, &'static str> {
+    let (mut unused_cards, counts) = get_draws(kept, dead_cards)?;
+    if trials == 0 {
+        return Err("At least one trial is needed");
+    }
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut totals = EquityTotals::new(kept.len());
+    for _ in 0..trials {
+        unused_cards.shuffle(&mut rng);
+        totals.add(&get_deuce_to_seven_shares(kept, &deal_from(&unused_cards, &counts)));
+    }
+    Ok(totals.into_equity())
+}
+
+/// The best eight-or-better low using exactly two hole cards and three board cards
+pub fn get_omaha_eight_or_better_low(hole: &[Card], board: &[Card]) -> Option<LowHand> {
+    let mut best = None;
+    for two_of_hole in hole.iter().combinations(2) {
+        for three_of_board in board.iter().combinations(3) {
+            let hand: Vec<Card> = two_of_hole.iter().chain(three_of_board.iter()).map(|card| **card).collect();
+            best = best.max(get_eight_or_better_low(&hand));
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+
+    fn low(cards: &str) -> Option<LowHand> {
+        get_eight_or_better_low(&parse_cards(cards).unwrap())
+    }
+
+    #[test]
+    fn test_get_eight_or_better_low() {
+        assert_eq!(low("Ah2d3c4s5h").unwrap().values(), vec![5, 4, 3, 2, 1]);
+        assert_eq!(low("Ah2d3c4s5hKdKc").unwrap().values(), vec![5, 4, 3, 2, 1]);
+        assert_eq!(low("8h7d6c4s2hAd3c").unwrap().values(), vec![6, 4, 3, 2, 1]);
+        assert!(low("Ah2d3c4s9h").is_none());
+        assert!(low("Ah2d3c4sAdKd2c").is_none());
+
+        // The highest card decides first, then the next highest
+        assert!(low("Ah2d3c4s5h") > low("Ah2d3c4s6h"));
+        assert!(low("8h5d4c3s2h") > low("8h6d3c2sAh"));
+        assert!(low("7h6d5c4s3h") > low("8hAd2c3s4h"));
+    }
+
+    #[test]
+    fn test_get_ace_to_five_low() {
+        let low = |cards: &str| get_ace_to_five_low(&parse_cards(cards).unwrap());
+        // Straights and flushes do not count, so the wheel is the nuts
+        assert_eq!(low("Ah2h3h4h5h").values(), vec![5, 4, 3, 2, 1]);
+        assert_eq!(low("Ah2h3h4h5h"), low("As2d3c4h5s"));
+        assert!(low("Ah2d3c4s5h") > low("Ah2d3c4s6h"));
+        assert!(low("KhQdJcTs9h") > low("AhAd2c3s4h"));
+        // Pairs count against the hand, and a lower pair beats a higher one
+        assert!(low("AhAd2c3s4h") > low("2h2d3cAs4h"));
+        assert!(low("AhAd2c3s4h") > low("AhAd2c2s4h"));
+        assert_eq!(low("Ah2d3c4s5hKdKc").values(), vec![5, 4, 3, 2, 1]);
+        // With only four values the best hand pairs the lowest card, listed first
+        assert_eq!(low("AhAd2c2s3h3d4c").values(), vec![1, 4, 3, 2]);
+        // An eight-or-better low is the same as its ace-to-five low
+        assert_eq!(Some(low("8h5d4c3s2h")), get_eight_or_better_low(&parse_cards("8h5d4c3s2h").unwrap()));
+    }
+
+    #[test]
+    fn test_get_deuce_to_seven_low() {
+        let low = |cards: &str| get_deuce_to_seven_low(&parse_cards(cards).unwrap());
+        assert_eq!(low("7h5d4c3s2h").values(), vec![7, 5, 4, 3, 2]);
+        assert!(low("7h5d4c3s2h") > low("7h6d4c3s2h"));
+        assert!(low("8h6d4c3s2h") > low("8h6d5c3s2h"));
+        // Aces are high, and straights and flushes count against the hand
+        assert!(low("8h6d4c3s2h") > low("Ah2d3c4s5h"));
+        assert!(low("Ah2d3c4s5h") > low("AhKd3c4s5h"));
+        assert!(low("KhQdJc9s7h") > low("6h5d4c3s2h"));
+        assert!(low("KhQdJc9s7d") > low("KhQhJh9h7h"));
+        assert!(low("KhQdJc9s7h") > low("2h2d3c4s5h"));
+        assert_eq!(low("Ah2h3h4h5h").values(), vec![14, 5, 4, 3, 2]);
+        // The best five of more cards
+        assert_eq!(low("7h5d4c3s2hKdKc").values(), vec![7, 5, 4, 3, 2]);
+    }
+
+    #[test]
+    fn test_get_deuce_to_seven_equity() {
+        let kept = [parse_cards("7h5d4c3s").unwrap(), parse_cards("8h6d4d3d2s").unwrap()];
+        let equity = get_deuce_to_seven_equity(&kept, &[]).unwrap();
+        assert!((equity[0].equity + equity[1].equity - 1.0).abs() < 1e-9);
+        // Only a deuce wins for the one card draw, so seeing every other deuce discarded leaves
+        // the drawing hand with nothing
+        let dead = parse_cards("2h2c2d").unwrap();
+        let dead_equity = get_deuce_to_seven_equity(&kept, &dead).unwrap();
+        assert!(equity[0].equity > 0.0);
+        assert_eq!(dead_equity[0].equity, 0.0);
+        assert!(get_deuce_to_seven_equity(&kept[..1], &[]).is_err());
+        assert!(get_deuce_to_seven_equity(&kept, &parse_cards("7h").unwrap()).is_err());
+
+        // Drawing three each is over a hundred million draws, so it has to be estimated
+        let kept = [parse_cards("7h5d").unwrap(), parse_cards("8h6d").unwrap()];
+        assert!(get_deuce_to_seven_equity(&kept, &[]).is_err());
+        let estimate = estimate_deuce_to_seven_equity(&kept, &[], 2000, 5).unwrap();
+        assert_eq!(estimate, estimate_deuce_to_seven_equity(&kept, &[], 2000, 5).unwrap());
+        assert!(estimate[0].equity > 0.5 && estimate[0].equity < 0.7);
+    }
+
+    #[test]
+    fn test_get_omaha_eight_or_better_low() {
+        let low = |hole: &str, board: &str| get_omaha_eight_or_better_low(&parse_cards(hole).unwrap(), &parse_cards(board).unwrap());
+        assert_eq!(low("Ah2dKcKs", "3c4s5hQdJc").unwrap().values(), vec![5, 4, 3, 2, 1]);
+        // Only two low cards on the board means no low is possible
+        assert!(low("Ah2d3c4s", "5h6dKcQsJd").is_none());
+        // An ace in hand that pairs the board still only counts once
+        assert_eq!(low("Ah3dKcKs", "Ac2s4h7dQc").unwrap().values(), vec![7, 4, 3, 2, 1]);
+        // Exactly two from the hand, even when more would be lower
+        assert_eq!(low("Ah2d3c4s", "5h7d8cKsQd").unwrap().values(), vec![8, 7, 5, 2, 1]);
+    }
+}
@@ -0,0 +1,198 @@
+//! Open-face Chinese Poker, where each player sets thirteen cards into a three card top row and
+//! five card middle and bottom rows. The bottom must be at least as strong as the middle and the
+//! middle at least as strong as the top, or the hand fouls.
+
+use crate::cards::{get_hand_rank, Card, Hand, HandRank};
+use crate::three_card::get_three_card_hand_rank;
+
+/// A completed Open-face Chinese Poker hand
+#[derive(Debug, Clone, PartialEq)]
+pub struct OfcBoard {
+    top: [Card; 3],
+    middle: [Card; 5],
+    bottom: [Card; 5],
+}
+
+/// Bonus points for a strong hand in the bottom row
+fn get_bottom_royalty(hand: Hand) -> i32 {
+    match hand {
+        Hand::Straight(_) => 2,
+        Hand::Flush(_) => 4,
+        Hand::FullHouse(_, _) => 6,
+        Hand::FourOfAKind(_) => 10,
+        Hand::StraightFlush(_) => 15,
+        Hand::RoyalFlush => 25,
+        _ => 0,
+    }
+}
+
+/// Bonus points for a strong hand in the middle row, double the bottom's from a straight up
+fn get_middle_royalty(hand: Hand) -> i32 {
+    match hand {
+        Hand::ThreeOfAKind(_) => 2,
+        hand => 2 * get_bottom_royalty(hand),
+    }
+}
+
+/// Bonus points for a pair of sixes or better, or any three of a kind, in the top row
+fn get_top_royalty(hand: Hand) -> i32 {
+    match hand {
+        Hand::Pair(value) if value >= 6 => value as i32 - 5,
+        Hand::ThreeOfAKind(value) => value as i32 + 8,
+        _ => 0,
+    }
+}
+
+impl OfcBoard {
+    pub fn build(top: [Card; 3], middle: [Card; 5], bottom: [Card; 5]) -> Result<OfcBoard, &'static str> {
+        let board = OfcBoard { top, middle, bottom };
+        let cards = board.cards();
+        if cards.iter().enumerate().any(|(index, card)| cards[..index].contains(card)) {
+            return Err("The same card cannot be used twice");
+        }
+        if cards.iter().any(|card| card.is_joker()) {
+            return Err("Open-face Chinese Poker is played without jokers");
+        }
+        Ok(board)
+    }
+
+    pub fn top(&self) -> &[Card] {
+        &self.top
+    }
+
+    pub fn middle(&self) -> &[Card] {
+        &self.middle
+    }
+
+    pub fn bottom(&self) -> &[Card] {
+        &self.bottom
+    }
+
+    fn cards(&self) -> Vec<Card> {
+        let mut cards = self.top.to_vec();
+        cards.extend(self.middle);
+        cards.extend(self.bottom);
+        cards
+    }
+
+    /// Ranks of the top, middle, and bottom rows, which compare with each other directly
+    pub fn get_row_ranks(&self) -> [HandRank; 3] {
+        [get_three_card_hand_rank(&self.top), get_hand_rank(&self.middle), get_hand_rank(&self.bottom)]
+    }
+
+    /// Whether a row is stronger than the row beneath it
+    pub fn is_foul(&self) -> bool {
+        let [top, middle, bottom] = self.get_row_ranks();
+        top > middle || middle > bottom
+    }
+
+    /// Bonus points for the top, middle, and bottom rows. A fouled hand earns none.
+    pub fn get_royalties(&self) -> [i32; 3] {
+        if self.is_foul() {
+            return [0; 3];
+        }
+        let [top, middle, bottom] = self.get_row_ranks();
+        [get_top_royalty(top.hand()), get_middle_royalty(middle.hand()), get_bottom_royalty(bottom.hand())]
+    }
+
+    pub fn get_total_royalties(&self) -> i32 {
+        self.get_royalties().iter().sum()
+    }
+
+    /// Whether the hand earns a Fantasyland hand: a pair of queens or better in the top row
+    /// without fouling
+    pub fn qualifies_for_fantasyland(&self) -> bool {
+        !self.is_foul() && self.get_row_ranks()[0] >= HandRank::new(1, &[12])
+    }
+
+    /// Cards dealt in the Fantasyland hand under progressive rules, where a stronger top row
+    /// earns more: 14 for queens, 15 for kings, 16 for aces, and 17 for three of a kind
+    pub fn get_fantasyland_cards(&self) -> Option<usize> {
+        if !self.qualifies_for_fantasyland() {
+            return None;
+        }
+        match self.get_row_ranks()[0].hand() {
+            Hand::Pair(value) => Some(value as usize + 2),
+            _ => Some(17),
+        }
+    }
+
+    /// Whether a hand set in Fantasyland earns another one: three of a kind in the top row, a
+    /// full house or better in the middle, or four of a kind or better in the bottom
+    pub fn stays_in_fantasyland(&self) -> bool {
+        if self.is_foul() {
+            return false;
+        }
+        let [top, middle, bottom] = self.get_row_ranks();
+        top.hand() >= Hand::ThreeOfAKind(2) || middle.hand() >= Hand::FullHouse(2, 2) || bottom.hand() >= Hand::FourOfAKind(2)
+    }
+
+    /// Points this hand wins from `other`, or loses as a negative number, under the usual 1-6
+    /// scoring: a point for each row won, three more for winning all three, plus the difference
+    /// in royalties. A fouled hand loses every row; two fouled hands score nothing.
+    pub fn score_against(&self, other: &OfcBoard) -> i32 {
+        let rows = match (self.is_foul(), other.is_foul()) {
+            (true, true) => return 0,
+            (true, false) => -6,
+            (false, true) => 6,
+            (false, false) => {
+                let rows: i32 = self.get_row_ranks().iter().zip(other.get_row_ranks())
+                    .map(|(mine, theirs)| mine.cmp(&theirs) as i32)
+                    .sum();
+                if rows.abs() == 3 { 2 * rows } else { rows }
+            }
+        };
+        rows + self.get_total_royalties() - other.get_total_royalties()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+
+    fn board(top: &str, middle: &str, bottom: &str) -> OfcBoard {
+        let (top, middle, bottom) = (parse_cards(top).unwrap(), parse_cards(middle).unwrap(), parse_cards(bottom).unwrap());
+        OfcBoard::build(top.try_into().unwrap(), middle.try_into().unwrap(), bottom.try_into().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_foul_and_royalties() {
+        let hand = board("QhQd2c", "9s9c9d5h4h", "AsKsTs7s3s");
+        assert!(!hand.is_foul());
+        assert_eq!(hand.get_royalties(), [7, 2, 4]);
+        assert!(hand.qualifies_for_fantasyland());
+        assert_eq!(hand.get_fantasyland_cards(), Some(14));
+        assert!(!hand.stays_in_fantasyland());
+
+        // A pair on top above a high card middle fouls and earns nothing
+        let fouled = board("QhQd2c", "As9c8d5h4h", "AdKsTs7s3s");
+        assert!(fouled.is_foul());
+        assert_eq!(fouled.get_total_royalties(), 0);
+        assert!(!fouled.qualifies_for_fantasyland());
+
+        let trips = board("2h2d2c", "KsKcKdQhQd", "AsAhAcAd3s");
+        assert_eq!(trips.get_royalties(), [10, 12, 10]);
+        assert_eq!(trips.get_fantasyland_cards(), Some(17));
+        assert!(trips.stays_in_fantasyland());
+        assert!(OfcBoard::build(trips.top, trips.middle, trips.middle).is_err());
+    }
+
+    #[test]
+    fn test_score_against() {
+        let strong = board("QhQd2c", "9s9c9d5h4h", "AsKsTs7s3s");
+        let weak = board("Jh3d2h", "8s8c6d5c4c", "AhAdKcKd3c");
+        // Every row plus the scoop bonus, and 13 in royalties to none
+        assert_eq!(strong.score_against(&weak), 6 + 13);
+        assert_eq!(weak.score_against(&strong), -(6 + 13));
+
+        let fouled = board("KhKd2s", "Ac8c6c5d4d", "JhTh9h8d7h");
+        assert!(fouled.is_foul());
+        assert_eq!(weak.score_against(&fouled), 6);
+        assert_eq!(fouled.score_against(&fouled.clone()), 0);
+
+        // Splitting the rows two to one
+        let split = board("AhKd2s", "7s7c6d5c4c", "QcQdJhJd3c");
+        assert_eq!(split.score_against(&weak), 1 - 1 - 1);
+    }
+}
@@ -0,0 +1,569 @@
+//! The Open Hand History JSON standard. Hands are read into the same `HandRecord` as text hand
+//! histories, and any `HandRecord`, including simulated ones, can be written back out.
+//!
+//! Open Hand History has no action for an uncalled bet being returned, so those are left out
+//! when writing; the pots still show what each player won.
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::cards::{Card, Variant};
+use crate::history::{Action, ActionKind, HandRecord, Player, Round};
+
+const SPEC_VERSION: &str = "1.4.6";
+const SITE_NAME: &str = "poker_odds";
+/// Written for hands without a known start time
+const UNKNOWN_START_DATE: &str = "1970-01-01T00:00:00Z";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OhhFile {
+    ohh: Ohh,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Ohh {
+    spec_version: String,
+    site_name: String,
+    network_name: String,
+    internal_version: String,
+    #[serde(default)]
+    tournament: bool,
+    game_number: String,
+    start_date_utc: String,
+    table_name: String,
+    game_type: String,
+    bet_limit: OhhBetLimit,
+    table_size: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    currency: Option<String>,
+    dealer_seat: usize,
+    small_blind_amount: f64,
+    big_blind_amount: f64,
+    ante_amount: f64,
+    #[serde(default)]
+    flags: Vec<String>,
+    players: Vec<OhhPlayer>,
+    rounds: Vec<OhhRound>,
+    pots: Vec<OhhPot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OhhBetLimit {
+    bet_type: String,
+    /// Zero when there is no cap
+    #[serde(default)]
+    bet_cap: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OhhPlayer {
+    id: usize,
+    seat: usize,
+    name: String,
+    starting_stack: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OhhRound {
+    id: usize,
+    street: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cards: Vec<String>,
+    actions: Vec<OhhAction>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OhhAction {
+    action_number: usize,
+    player_id: usize,
+    action: String,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    amount: f64,
+    #[serde(default)]
+    is_allin: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cards: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OhhPot {
+    number: usize,
+    amount: f64,
+    #[serde(default)]
+    rake: f64,
+    player_wins: Vec<OhhWin>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OhhWin {
+    player_id: usize,
+    win_amount: f64,
+}
+
+fn is_zero(amount: &f64) -> bool {
+    *amount == 0.0
+}
+
+/// The currency code at the end of stakes such as "($0.01/$0.02 USD)"
+fn get_currency(description: &str) -> Option<String> {
+    let stakes = description.rsplit_once('(')?.1.strip_suffix(')')?;
+    let code = stakes.split_whitespace().last()?;
+    (code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase())).then(|| code.to_string())
+}
+
+/// Removes the error left by subtracting amounts such as $1.86 - $0.20
+fn round_amount(amount: f64) -> f64 {
+    (amount * 1e6).round() / 1e6
+}
+
+const ROUNDS: [(Round, &str); 5] = [
+    (Round::Preflop, "Preflop"),
+    (Round::Flop, "Flop"),
+    (Round::Turn, "Turn"),
+    (Round::River, "River"),
+    (Round::Showdown, "Showdown"),
+];
+
+fn to_strings(cards: &[Card]) -> Vec<String> {
+    cards.iter().map(|card| card.to_string()).collect()
+}
+
+/// The Open Hand History name and amount of an action, or `None` for actions it cannot express
+fn get_action(kind: &ActionKind) -> Option<(&'static str, f64, Vec<String>)> {
+    let action = match kind {
+        ActionKind::PostSmallBlind(amount) => ("Post SB", *amount, Vec::new()),
+        ActionKind::PostBigBlind(amount) => ("Post BB", *amount, Vec::new()),
+        ActionKind::PostAnte(amount) => ("Post Ante", *amount, Vec::new()),
+        ActionKind::Post(amount) => ("Post Extra Blind", *amount, Vec::new()),
+        ActionKind::Fold => ("Fold", 0.0, Vec::new()),
+        ActionKind::Check => ("Check", 0.0, Vec::new()),
+        ActionKind::Call(amount) => ("Call", *amount, Vec::new()),
+        ActionKind::Bet(amount) => ("Bet", *amount, Vec::new()),
+        // Raises are written as the total raised to
+        ActionKind::Raise(_, to) => ("Raise", *to, Vec::new()),
+        ActionKind::Show(cards) => ("Shows Cards", 0.0, to_strings(cards)),
+        ActionKind::Muck => ("Mucks Cards", 0.0, Vec::new()),
+        ActionKind::UncalledBetReturned(_) => return None,
+    };
+    Some(action)
+}
+
+/// Writes a hand as an Open Hand History JSON document. Players are identified by their seat.
+pub fn write_ohh(record: &HandRecord) -> Result<String, &'static str> {
+    let game_type = match record.variant() {
+        Some(Variant::Holdem) => "Holdem",
+        Some(Variant::Omaha) => "Omaha",
+        Some(Variant::OmahaHiLo) => "OmahaHiLo",
+        _ => return Err("The hand's game is not supported by Open Hand History"),
+    };
+    let bet_type = if record.description.contains("No Limit") {
+        "NL"
+    } else if record.description.contains("Pot Limit") {
+        "PL"
+    } else {
+        "FL"
+    };
+    let seats: HashMap<&str, usize> = record.players.iter().map(|player| (player.name.as_str(), player.seat)).collect();
+    let seat_of = |name: &str| seats.get(name).copied().ok_or("An action is by a player who is not seated");
+    let forced_bet = |matches: fn(&ActionKind) -> Option<f64>| record.actions.iter().find_map(|action| matches(&action.kind)).unwrap_or(0.0);
+
+    let mut action_number = 0;
+    let mut rounds = Vec::new();
+    for (round, street) in ROUNDS {
+        let cards = match round {
+            Round::Flop => record.flop.map(|flop| flop.to_vec()).unwrap_or_default(),
+            Round::Turn => record.turn.into_iter().collect(),
+            Round::River => record.river.into_iter().collect(),
+            _ => Vec::new(),
+        };
+        let mut actions = Vec::new();
+        let mut add = |player_id, (action, amount, cards): (&str, f64, Vec<String>), is_allin| {
+            action_number += 1;
+            actions.push(OhhAction { action_number, player_id, action: action.to_string(), amount, is_allin, cards });
+        };
+        let round_actions: Vec<&Action> = record.actions_in(round).collect();
+        let posts = round_actions.iter()
+            .take_while(|action| matches!(action.kind, ActionKind::PostSmallBlind(_) | ActionKind::PostBigBlind(_) | ActionKind::PostAnte(_) | ActionKind::Post(_)))
+            .count();
+        for index in 0..=round_actions.len() {
+            if round == Round::Preflop && index == posts {
+                // Hole cards not shown later were dealt face down to the history's owner
+                for player in &record.players {
+                    let is_shown = record.actions.iter().any(|action| action.player == player.name && matches!(action.kind, ActionKind::Show(_)));
+                    if let (Some(hole), false) = (&player.hole, is_shown) {
+                        add(player.seat, ("Dealt Cards", 0.0, to_strings(hole)), false);
+                    }
+                }
+            }
+            let Some(action) = round_actions.get(index) else { break };
+            if let Some(ohh_action) = get_action(&action.kind) {
+                add(seat_of(&action.player)?, ohh_action, action.is_all_in);
+            }
+        }
+        if round == Round::Preflop || !cards.is_empty() || !actions.is_empty() {
+            rounds.push(OhhRound { id: rounds.len(), street: street.to_string(), cards: to_strings(&cards), actions });
+        }
+    }
+
+    let player_wins = record.winners.iter()
+        .map(|(name, win_amount)| Ok(OhhWin { player_id: seat_of(name)?, win_amount: *win_amount }))
+        .collect::<Result<Vec<OhhWin>, &'static str>>()?;
+    let ohh = Ohh {
+        spec_version: SPEC_VERSION.to_string(),
+        site_name: SITE_NAME.to_string(),
+        network_name: SITE_NAME.to_string(),
+        internal_version: env!("CARGO_PKG_VERSION").to_string(),
+        tournament: false,
+        game_number: record.id.clone(),
+        start_date_utc: record.start_date_utc.clone().unwrap_or_else(|| UNKNOWN_START_DATE.to_string()),
+        table_name: record.table.clone(),
+        game_type: game_type.to_string(),
+        bet_limit: OhhBetLimit { bet_type: bet_type.to_string(), bet_cap: 0.0 },
+        table_size: record.players.iter().map(|player| player.seat).max().unwrap_or(0).max(2),
+        currency: get_currency(&record.description),
+        dealer_seat: record.button,
+        small_blind_amount: forced_bet(|kind| if let ActionKind::PostSmallBlind(amount) = kind { Some(*amount) } else { None }),
+        big_blind_amount: forced_bet(|kind| if let ActionKind::PostBigBlind(amount) = kind { Some(*amount) } else { None }),
+        ante_amount: forced_bet(|kind| if let ActionKind::PostAnte(amount) = kind { Some(*amount) } else { None }),
+        flags: Vec::new(),
+        players: record.players.iter()
+            .map(|player| OhhPlayer { id: player.seat, seat: player.seat, name: player.name.clone(), starting_stack: player.stack })
+            .collect(),
+        rounds,
+        pots: vec![OhhPot { number: 0, amount: record.pot, rake: record.rake, player_wins }],
+    };
+    serde_json::to_string_pretty(&OhhFile { ohh }).map_err(|_| "The hand could not be written as JSON")
+}
+
+fn parse_ohh_cards(cards: &[String]) -> Result<Vec<Card>, &'static str> {
+    cards.iter().map(|card| card.parse()).collect()
+}
+
+fn to_record(ohh: Ohh) -> Result<HandRecord, &'static str> {
+    let game = match ohh.game_type.as_str() {
+        "Holdem" => "Hold'em",
+        "Omaha" => "Omaha",
+        "OmahaHiLo" => "Omaha Hi/Lo",
+        _ => return Err("Only Hold'em and Omaha hands are supported"),
+    };
+    let limit = match ohh.bet_limit.bet_type.as_str() {
+        "NL" => "No Limit",
+        "PL" => "Pot Limit",
+        _ => "Limit",
+    };
+    let names: HashMap<usize, String> = ohh.players.iter().map(|player| (player.id, player.name.clone())).collect();
+    let name_of = |id: &usize| names.get(id).cloned().ok_or("An action is by a player who is not seated");
+    let mut record = HandRecord {
+        id: ohh.game_number,
+        description: match &ohh.currency {
+            Some(currency) => format!("{game} {limit} ({currency})"),
+            None => format!("{game} {limit}"),
+        },
+        table: ohh.table_name,
+        button: ohh.dealer_seat,
+        players: ohh.players.iter()
+            .map(|player| Player { seat: player.seat, name: player.name.clone(), stack: player.starting_stack, hole: None })
+            .collect(),
+        flop: None,
+        turn: None,
+        river: None,
+        actions: Vec::new(),
+        pot: 0.0,
+        rake: 0.0,
+        winners: Vec::new(),
+        start_date_utc: Some(ohh.start_date_utc),
+    };
+    let mut holes: Vec<(String, Vec<Card>)> = Vec::new();
+    for ohh_round in &ohh.rounds {
+        let round = ROUNDS.iter().find(|(_, street)| *street == ohh_round.street).ok_or("Unknown street")?.0;
+        let cards = parse_ohh_cards(&ohh_round.cards)?;
+        match round {
+            Round::Flop => record.flop = Some(cards.try_into().map_err(|_| "The flop must have three cards")?),
+            Round::Turn => record.turn = Some(*cards.first().ok_or("Expected the turn card")?),
+            Round::River => record.river = Some(*cards.first().ok_or("Expected the river card")?),
+            _ => (),
+        }
+        // What each player has put in this round, to turn raises back into their size
+        let mut bets: HashMap<usize, f64> = HashMap::new();
+        for action in &ohh_round.actions {
+            let current_bet = bets.values().copied().fold(0.0, f64::max);
+            let bet = bets.entry(action.player_id).or_insert(0.0);
+            let amount = action.amount;
+            let kind = match action.action.as_str() {
+                "Dealt Cards" | "Dealt Card" => {
+                    holes.push((name_of(&action.player_id)?, parse_ohh_cards(&action.cards)?));
+                    continue;
+                }
+                "Shows Cards" => {
+                    let cards = parse_ohh_cards(&action.cards)?;
+                    holes.push((name_of(&action.player_id)?, cards.clone()));
+                    ActionKind::Show(cards)
+                }
+                "Mucks Cards" => ActionKind::Muck,
+                "Post SB" => ActionKind::PostSmallBlind(amount),
+                "Post BB" => ActionKind::PostBigBlind(amount),
+                "Post Ante" => ActionKind::PostAnte(amount),
+                "Post Extra Blind" | "Post Dead" | "Straddle" => ActionKind::Post(amount),
+                "Fold" => ActionKind::Fold,
+                "Check" => ActionKind::Check,
+                "Call" => ActionKind::Call(amount),
+                "Bet" => ActionKind::Bet(amount),
+                "Raise" => ActionKind::Raise(round_amount(amount - current_bet), amount),
+                // Seating changes and the like do not affect the hand
+                _ => continue,
+            };
+            match kind {
+                ActionKind::Raise(_, to) => *bet = to,
+                ActionKind::PostSmallBlind(amount) | ActionKind::PostBigBlind(amount) | ActionKind::Post(amount)
+                | ActionKind::Call(amount) | ActionKind::Bet(amount) => *bet = round_amount(*bet + amount),
+                _ => (),
+            }
+            record.actions.push(Action { player: name_of(&action.player_id)?, round, kind, is_all_in: action.is_allin });
+        }
+    }
+    for (name, hole) in holes {
+        if let Some(player) = record.players.iter_mut().find(|player| player.name == name) {
+            player.hole = Some(hole);
+        }
+    }
+    for pot in &ohh.pots {
+        record.pot += pot.amount;
+        record.rake += pot.rake;
+        for win in &pot.player_wins {
+            record.winners.push((name_of(&win.player_id)?, win.win_amount));
+        }
+    }
+    Ok(record)
+}
+
+/// Reads one Open Hand History JSON document
+pub fn read_ohh(json: &str) -> Result<HandRecord, &'static str> {
+    let file: OhhFile = serde_json::from_str(json).map_err(|_| "Invalid Open Hand History JSON")?;
+    to_record(file.ohh)
+}
+
+/// Reads every hand in an Open Hand History file, where documents follow one another
+pub fn read_ohh_file(text: &str) -> Result<Vec<HandRecord>, &'static str> {
+    serde_json::Deserializer::from_str(text)
+        .into_iter::<OhhFile>()
+        .map(|file| to_record(file.map_err(|_| "Invalid Open Hand History JSON")?.ohh))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+    use crate::history::parse_hand_history;
+
+    /// Checks the written document against the Open Hand History 1.4 specification: every field
+    /// it lists is present with its type, and enumerated fields only take the values it allows
+    fn check_ohh(json: &str) -> Result<(), String> {
+        use serde_json::Value;
+        let document: Value = serde_json::from_str(json).map_err(|error| error.to_string())?;
+        let field = |object: &Value, name: &str, is_type: fn(&Value) -> bool| -> Result<(), String> {
+            match object.get(name) {
+                Some(value) if is_type(value) => Ok(()),
+                Some(value) => Err(format!("{name} has the wrong type: {value}")),
+                None => Err(format!("missing {name} in {object}")),
+            }
+        };
+        let fields = |object: &Value, names: &[&str], is_type: fn(&Value) -> bool| {
+            names.iter().try_for_each(|name| field(object, name, is_type))
+        };
+        let one_of = |object: &Value, name: &str, allowed: &[&str]| match object[name].as_str() {
+            Some(value) if allowed.contains(&value) => Ok(()),
+            _ => Err(format!("{name} is not one of {allowed:?}: {}", object[name])),
+        };
+        let cards = |object: &Value| match object.get("cards") {
+            None => Ok(()),
+            Some(Value::Array(cards)) if cards.iter().all(|card| card.as_str().is_some_and(|card| card.parse::<Card>().is_ok())) => Ok(()),
+            Some(value) => Err(format!("cards must be a list of cards: {value}")),
+        };
+        let number = |value: &Value| value.is_number() && value.as_f64().is_some_and(|number| number >= 0.0);
+        let ohh = &document["ohh"];
+        fields(ohh, &["spec_version", "site_name", "network_name", "internal_version", "game_number", "start_date_utc", "table_name"], Value::is_string)?;
+        fields(ohh, &["tournament"], Value::is_boolean)?;
+        fields(ohh, &["table_size", "dealer_seat"], Value::is_u64)?;
+        fields(ohh, &["small_blind_amount", "big_blind_amount", "ante_amount"], number)?;
+        fields(ohh, &["bet_limit"], Value::is_object)?;
+        fields(ohh, &["flags", "players", "rounds", "pots"], Value::is_array)?;
+        if ohh.get("currency").is_some() {
+            field(ohh, "currency", Value::is_string)?;
+        }
+        one_of(ohh, "game_type", &["Holdem", "Omaha", "OmahaHiLo", "Stud", "StudHiLo", "Draw"])?;
+        one_of(&ohh["bet_limit"], "bet_type", &["NL", "PL", "FL"])?;
+        field(&ohh["bet_limit"], "bet_cap", number)?;
+        for player in ohh["players"].as_array().unwrap() {
+            fields(player, &["id", "seat"], Value::is_u64)?;
+            field(player, "name", Value::is_string)?;
+            field(player, "starting_stack", number)?;
+        }
+        for round in ohh["rounds"].as_array().unwrap() {
+            field(round, "id", Value::is_u64)?;
+            one_of(round, "street", &["Preflop", "Flop", "Turn", "River", "Showdown"])?;
+            field(round, "actions", Value::is_array)?;
+            cards(round)?;
+            for action in round["actions"].as_array().unwrap() {
+                fields(action, &["action_number", "player_id"], Value::is_u64)?;
+                one_of(action, "action", &[
+                    "Dealt Cards", "Mucks Cards", "Shows Cards", "Post Ante", "Post SB", "Post BB", "Straddle",
+                    "Post Dead", "Post Extra Blind", "Fold", "Check", "Bet", "Raise", "Call",
+                ])?;
+                if action.get("amount").is_some() {
+                    field(action, "amount", number)?;
+                }
+                if action.get("is_allin").is_some() {
+                    field(action, "is_allin", Value::is_boolean)?;
+                }
+                cards(action)?;
+            }
+        }
+        for pot in ohh["pots"].as_array().unwrap() {
+            field(pot, "number", Value::is_u64)?;
+            fields(pot, &["amount", "rake"], number)?;
+            field(pot, "player_wins", Value::is_array)?;
+            for win in pot["player_wins"].as_array().unwrap() {
+                field(win, "player_id", Value::is_u64)?;
+                field(win, "win_amount", number)?;
+            }
+        }
+        Ok(())
+    }
+
+    const HAND: &str = "\
+PokerStars Hand #222:  Hold'em No Limit ($0.01/$0.02 USD) - 2020/01/01 12:00:00 ET
+Table 'Beta' 6-max Seat #1 is the button
+Seat 1: Alice ($2.00 in chips)
+Seat 2: Bob ($2.00 in chips)
+Seat 4: Carol ($1.50 in chips)
+Alice: posts small blind $0.01
+Bob: posts big blind $0.02
+*** HOLE CARDS ***
+Dealt to Carol [9s 9h]
+Carol: raises $0.04 to $0.06
+Alice: folds
+Bob: calls $0.04
+*** FLOP *** [2h 7d 9c]
+Bob: checks
+Carol: bets $0.08
+Bob: calls $0.08
+*** TURN *** [2h 7d 9c] [Js]
+Bob: bets $0.20
+Carol: raises $1.16 to $1.36 and is all-in
+Bob: calls $1.16
+*** RIVER *** [2h 7d 9c Js] [4s]
+*** SHOW DOWN ***
+Bob: shows [Jd Jc] (three of a kind, Jacks)
+Carol: mucks hand
+Bob collected $2.98 from pot
+*** SUMMARY ***
+Total pot $3.01 | Rake $0.03
+";
+
+    #[test]
+    fn test_round_trip() {
+        let record = parse_hand_history(HAND).unwrap();
+        let json = write_ohh(&record).unwrap();
+        check_ohh(&json).unwrap();
+        let read = read_ohh(&json).unwrap();
+        assert_eq!(read.id, record.id);
+        assert_eq!(read.players, record.players);
+        assert_eq!((read.flop, read.turn, read.river), (record.flop, record.turn, record.river));
+        assert_eq!(read.actions, record.actions);
+        assert_eq!((read.pot, read.rake), (record.pot, record.rake));
+        assert_eq!(read.winners, record.winners);
+        assert_eq!(read.variant(), Some(Variant::Holdem));
+        assert_eq!(write_ohh(&read).unwrap().replace(UNKNOWN_START_DATE, ""), json.replace(UNKNOWN_START_DATE, ""));
+
+        // The check fails on a wrongly typed street, an unknown action or a missing field
+        assert!(check_ohh(&json.replacen("\"street\": \"Flop\"", "\"street\": 3", 1)).is_err());
+        assert!(check_ohh(&json.replacen("\"action\": \"Raise\"", "\"action\": \"Reraise\"", 1)).is_err());
+        assert!(check_ohh(&json.replacen("\"bet_type\": \"NL\"", "\"bet_type\": \"No Limit\"", 1)).is_err());
+        assert!(check_ohh(&json.replacen("\"dealer_seat\"", "\"button_seat\"", 1)).is_err());
+    }
+
+    #[test]
+    fn test_read_ohh() {
+        let json = r#"{"ohh": {
+            "spec_version": "1.4.6", "site_name": "Example", "network_name": "Example", "internal_version": "2",
+            "tournament": false, "game_number": "77", "start_date_utc": "2023-05-01T18:00:00Z",
+            "table_name": "Gamma", "game_type": "Omaha", "bet_limit": {"bet_type": "PL", "bet_cap": 0},
+            "table_size": 6, "currency": "USD", "dealer_seat": 2, "small_blind_amount": 1,
+            "big_blind_amount": 2, "ante_amount": 0, "hero_player_id": 1, "flags": [],
+            "players": [
+                {"id": 1, "seat": 1, "name": "Hero", "display": "Hero", "starting_stack": 200},
+                {"id": 2, "seat": 2, "name": "Villain", "starting_stack": 150}
+            ],
+            "rounds": [
+                {"id": 0, "street": "Preflop", "actions": [
+                    {"action_number": 1, "player_id": 2, "action": "Post SB", "amount": 1},
+                    {"action_number": 2, "player_id": 1, "action": "Post BB", "amount": 2},
+                    {"action_number": 3, "player_id": 1, "action": "Dealt Cards", "cards": ["Ah", "Ad", "Ks", "Qs"]},
+                    {"action_number": 4, "player_id": 2, "action": "Raise", "amount": 6},
+                    {"action_number": 5, "player_id": 1, "action": "Raise", "amount": 18},
+                    {"action_number": 6, "player_id": 2, "action": "Call", "amount": 12}
+                ]},
+                {"id": 1, "street": "Flop", "cards": ["2c", "7d", "Ts"], "actions": [
+                    {"action_number": 7, "player_id": 1, "action": "Bet", "amount": 36},
+                    {"action_number": 8, "player_id": 2, "action": "Fold"}
+                ]}
+            ],
+            "pots": [{"number": 0, "amount": 72, "rake": 0, "player_wins": [{"player_id": 1, "win_amount": 72}]}]
+        }}"#;
+        check_ohh(json).unwrap();
+        let record = read_ohh(json).unwrap();
+        assert_eq!(record.variant(), Some(Variant::Omaha));
+        assert_eq!(record.description, "Omaha Pot Limit (USD)");
+        assert_eq!(record.player("Hero").unwrap().hole, Some(parse_cards("AhAdKsQs").unwrap()));
+        assert_eq!(record.actions[3].kind, ActionKind::Raise(12.0, 18.0));
+        assert_eq!(record.flop, Some(parse_cards("2c7dTs").unwrap().try_into().unwrap()));
+        assert_eq!(record.winners, vec![("Hero".to_string(), 72.0)]);
+        assert_eq!(record.start_date_utc.as_deref(), Some("2023-05-01T18:00:00Z"));
+        assert!(record.to_game("Hero", Round::Flop).is_ok());
+
+        let file = format!("{json}\n\n{json}");
+        assert_eq!(read_ohh_file(&file).unwrap().len(), 2);
+        assert!(read_ohh("{\"ohh\": {}}").is_err());
+        assert!(read_ohh(&json.replace("\"Ts\"", "\"Tx\"")).is_err());
+    }
+
+    #[test]
+    fn test_write_constructed_hand() {
+        // A hand built in code rather than read from a history, as a simulation would
+        let players = vec![
+            Player { seat: 1, name: "Sim 1".to_string(), stack: 100.0, hole: Some(parse_cards("AhKh").unwrap()) },
+            Player { seat: 2, name: "Sim 2".to_string(), stack: 100.0, hole: None },
+        ];
+        let action = |player: &str, kind| Action { player: player.to_string(), round: Round::Preflop, kind, is_all_in: false };
+        let record = HandRecord {
+            id: "sim-1".to_string(),
+            description: "Hold'em No Limit".to_string(),
+            table: "Simulation".to_string(),
+            button: 1,
+            players,
+            flop: None,
+            turn: None,
+            river: None,
+            actions: vec![
+                action("Sim 1", ActionKind::PostSmallBlind(0.5)),
+                action("Sim 2", ActionKind::PostBigBlind(1.0)),
+                action("Sim 1", ActionKind::Raise(2.0, 3.0)),
+                action("Sim 2", ActionKind::Fold),
+                action("Sim 1", ActionKind::UncalledBetReturned(2.0)),
+            ],
+            pot: 2.0,
+            rake: 0.0,
+            winners: vec![("Sim 1".to_string(), 2.0)],
+            start_date_utc: Some("2024-01-01T00:00:00Z".to_string()),
+        };
+        let json = write_ohh(&record).unwrap();
+        check_ohh(&json).unwrap();
+        let read = read_ohh(&json).unwrap();
+        assert_eq!(read.actions, record.actions[..4]);
+        assert_eq!(read.players, record.players);
+
+        let mut stud = record.clone();
+        stud.description = "7 Card Stud Limit".to_string();
+        assert!(write_ohh(&stud).is_err());
+    }
+}
@@ -0,0 +1,381 @@
+//! Heads-up postflop subgames for study. Two ranges meet on a flop, turn or river with a pot
+//! and equal stacks behind, and betting follows a tree of bet and raise sizes through the
+//! remaining streets. The subgame is a `TwoPlayerGame`, so `CfrSolver` finds its equilibrium,
+//! and the strategy and EV of every combo can be read off at any point in the first street.
+
+use std::fmt;
+use crate::cards::{get_deck_without, Card};
+use crate::cfr::{get_action_probabilities, get_showdown_utility, get_state_value, Node, Profile, TwoPlayerGame};
+use crate::range::Range;
+
+/// Bet sizes as fractions of the pot. Raises are sized on the pot after calling. A player with
+/// chips behind can always move all-in unless `max_raises` has been reached, and sizes above
+/// the stack become all-ins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BetSizes {
+    pub bets: Vec<f64>,
+    pub raises: Vec<f64>,
+    /// Raises allowed on each street after the first bet
+    pub max_raises: usize,
+}
+
+/// An action in the subgame. Bets and raises give the player's total for the street.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PostflopAction {
+    Fold,
+    Check,
+    Call,
+    Bet(f64),
+    Raise(f64),
+}
+
+impl fmt::Display for PostflopAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PostflopAction::Fold => write!(f, "f"),
+            PostflopAction::Check => write!(f, "x"),
+            PostflopAction::Call => write!(f, "c"),
+            PostflopAction::Bet(to) => write!(f, "b{}", (to * 100.0).round() / 100.0),
+            PostflopAction::Raise(to) => write!(f, "r{}", (to * 100.0).round() / 100.0),
+        }
+    }
+}
+
+/// A heads-up subgame. The first player is out of position and acts first on every street.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostflopGame {
+    board: Vec<Card>,
+    pot: f64,
+    stack: f64,
+    sizes: BetSizes,
+    /// Every pair of hole cards the two ranges can hold together, all equally likely
+    deals: Vec<[[Card; 2]; 2]>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostflopState {
+    deal: Option<[[Card; 2]; 2]>,
+    board: Vec<Card>,
+    /// The actions so far, with "/" and the card dealt before each later street
+    history: String,
+    /// Chips each player has put in since the start of the subgame
+    invested: [f64; 2],
+    /// Chips each player has put in on this street
+    street: [f64; 2],
+    raises: usize,
+    to_act: usize,
+    checked: bool,
+    folded: Option<usize>,
+    street_over: bool,
+}
+
+/// The strategy of one combo at a point in the first street
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComboStrategy {
+    pub hole: [Card; 2],
+    /// Probability of each action, in the order they are listed
+    pub probabilities: Vec<f64>,
+    /// Chips the combo expects to take from the pot, less what it puts in after the start of
+    /// the subgame, with the opponent's combos weighted by how often they reach this point
+    pub ev: f64,
+}
+
+impl PostflopGame {
+    /// `ranges` are the first player's and then the second's, and `stack` is what each has
+    /// behind at the start of the subgame
+    pub fn build(
+        ranges: [&Range; 2],
+        flop: [Card; 3],
+        turn: Option<Card>,
+        river: Option<Card>,
+        pot: f64,
+        stack: f64,
+        sizes: BetSizes,
+    ) -> Result<PostflopGame, &'static str> {
+        if !(pot > 0.0 && pot.is_finite() && stack >= 0.0 && stack.is_finite()) {
+            return Err("The pot must be positive and the stacks cannot be negative");
+        }
+        if sizes.bets.iter().chain(&sizes.raises).any(|size| !(*size > 0.0 && size.is_finite())) {
+            return Err("Bet sizes must be positive");
+        }
+        if river.is_some() && turn.is_none() {
+            return Err("River cannot be dealt before the turn");
+        }
+        let mut board = flop.to_vec();
+        board.extend(turn);
+        board.extend(river);
+        if board.iter().enumerate().any(|(index, card)| board[..index].contains(card)) {
+            return Err("The same card cannot be used twice");
+        }
+        let mut deals = Vec::new();
+        for first in ranges[0].combos_without(&board) {
+            for second in ranges[1].combos_without(&[board.clone(), first.to_vec()].concat()) {
+                deals.push([first, second]);
+            }
+        }
+        if deals.is_empty() {
+            return Err("The ranges have no hands that fit together on the board");
+        }
+        Ok(PostflopGame { board, pot, stack, sizes, deals })
+    }
+
+    pub fn board(&self) -> &[Card] {
+        &self.board
+    }
+
+    fn is_all_in(&self, state: &PostflopState) -> bool {
+        state.invested.iter().any(|invested| *invested >= self.stack)
+    }
+
+    /// The actions open to the player to act
+    pub fn get_actions(&self, state: &PostflopState) -> Vec<PostflopAction> {
+        let player = state.to_act;
+        let to_call = state.street[1 - player] - state.street[player];
+        let remaining = self.stack - state.invested[player];
+        let pot = self.pot + state.invested.iter().sum::<f64>();
+        let all_in = state.street[player] + remaining;
+        let (mut actions, sizes) = if to_call > 0.0 {
+            let can_raise = state.raises < self.sizes.max_raises && remaining > to_call && !self.is_all_in(state);
+            let sizes = if can_raise {
+                self.sizes.raises.iter().map(|size| state.street[1 - player] + size * (pot + to_call)).chain([all_in]).collect()
+            } else {
+                Vec::new()
+            };
+            (vec![PostflopAction::Fold, PostflopAction::Call], sizes)
+        } else {
+            let sizes = if remaining > 0.0 { self.sizes.bets.iter().map(|size| size * pot).chain([all_in]).collect() } else { Vec::new() };
+            (vec![PostflopAction::Check], sizes)
+        };
+        let mut totals: Vec<f64> = sizes.into_iter().map(|to: f64| to.min(all_in)).collect();
+        totals.sort_by(f64::total_cmp);
+        totals.dedup_by(|a, b| (*a - *b).abs() < 1e-9);
+        let bet = |to| if to_call > 0.0 { PostflopAction::Raise(to) } else { PostflopAction::Bet(to) };
+        actions.extend(totals.into_iter().map(bet));
+        actions
+    }
+
+    /// Cards that can come next on the board
+    fn get_next_cards(&self, state: &PostflopState) -> Vec<Card> {
+        let [first, second] = state.deal.expect("Cards are dealt before the board");
+        get_deck_without(&[state.board.clone(), first.to_vec(), second.to_vec()].concat())
+    }
+
+    fn get_info_set(&self, state: &PostflopState) -> String {
+        let [first, second] = state.deal.expect("Cards are dealt before any action")[state.to_act];
+        format!("{first}{second}:{}", state.history)
+    }
+
+    /// Each combo's strategy and EV for the player to act after `line`, a list of action
+    /// indices from the start of the first street, along with the actions to choose from
+    pub fn get_strategies(&self, profile: &Profile, line: &[usize]) -> Result<(Vec<PostflopAction>, Vec<ComboStrategy>), &'static str> {
+        let mut actions = Vec::new();
+        let mut strategies: Vec<ComboStrategy> = Vec::new();
+        // Opponent reach and reach-weighted value for each combo, with unweighted fallbacks
+        let mut totals: Vec<[f64; 4]> = Vec::new();
+        // Players alternate on the first street, starting with the first player
+        let hero = line.len() % 2;
+        for index in 0..self.deals.len() {
+            let mut state = self.next(&self.root(), index);
+            let mut reach = 1.0;
+            for &action in line {
+                let Node::Decision { player, info_set, actions } = self.node(&state) else {
+                    return Err("The line must stay within the first street");
+                };
+                if action >= actions {
+                    return Err("The line chooses an action that does not exist");
+                }
+                if player != hero {
+                    reach *= get_action_probabilities(profile, &info_set, actions)[action];
+                }
+                state = self.next(&state, action);
+            }
+            let Node::Decision { player, info_set, actions: count } = self.node(&state) else {
+                return Err("The line must stay within the first street");
+            };
+            actions = self.get_actions(&state);
+            let hole = self.deals[index][player];
+            let value = self.pot / 2.0 + if player == 0 { 1.0 } else { -1.0 } * get_state_value(self, profile, &state);
+            let position = match strategies.iter().position(|strategy| strategy.hole == hole) {
+                Some(position) => position,
+                None => {
+                    strategies.push(ComboStrategy { hole, probabilities: get_action_probabilities(profile, &info_set, count), ev: 0.0 });
+                    totals.push([0.0; 4]);
+                    strategies.len() - 1
+                }
+            };
+            let total = &mut totals[position];
+            *total = [total[0] + reach, total[1] + reach * value, total[2] + 1.0, total[3] + value];
+        }
+        for (strategy, [reach, value, count, unweighted]) in strategies.iter_mut().zip(totals) {
+            strategy.ev = if reach > 0.0 { value / reach } else { unweighted / count };
+        }
+        Ok((actions, strategies))
+    }
+}
+
+impl TwoPlayerGame for PostflopGame {
+    type State = PostflopState;
+
+    fn root(&self) -> PostflopState {
+        PostflopState {
+            deal: None,
+            board: self.board.clone(),
+            history: String::new(),
+            invested: [0.0; 2],
+            street: [0.0; 2],
+            raises: 0,
+            to_act: 0,
+            checked: false,
+            folded: None,
+            street_over: false,
+        }
+    }
+
+    fn node(&self, state: &PostflopState) -> Node {
+        if state.deal.is_none() {
+            return Node::Chance(vec![1.0 / self.deals.len() as f64; self.deals.len()]);
+        }
+        // Each player is treated as having put in half the pot before the subgame
+        let stake = |player: usize| self.pot / 2.0 + state.invested[player];
+        if let Some(player) = state.folded {
+            return Node::Terminal(if player == 0 { -stake(0) } else { stake(1) });
+        }
+        if state.street_over {
+            if state.board.len() == 5 {
+                let [first, second] = state.deal.expect("Cards are dealt before the showdown");
+                return Node::Terminal(get_showdown_utility([&first, &second], &state.board, stake(0)));
+            }
+            let cards = self.get_next_cards(state).len();
+            return Node::Chance(vec![1.0 / cards as f64; cards]);
+        }
+        Node::Decision { player: state.to_act, info_set: self.get_info_set(state), actions: self.get_actions(state).len() }
+    }
+
+    fn next(&self, state: &PostflopState, index: usize) -> PostflopState {
+        let mut next = state.clone();
+        if state.deal.is_none() {
+            next.deal = Some(self.deals[index]);
+            return next;
+        }
+        if state.street_over {
+            let card = self.get_next_cards(state)[index];
+            next.board.push(card);
+            next.history.push_str(&format!("/{card}:"));
+            next.street = [0.0; 2];
+            next.raises = 0;
+            next.to_act = 0;
+            next.checked = false;
+            next.street_over = self.is_all_in(state);
+            return next;
+        }
+        let player = state.to_act;
+        let action = self.get_actions(state)[index];
+        next.history.push_str(&action.to_string());
+        match action {
+            PostflopAction::Fold => next.folded = Some(player),
+            PostflopAction::Check if state.checked => next.street_over = true,
+            PostflopAction::Check => next.checked = true,
+            PostflopAction::Call => {
+                let to_call = state.street[1 - player] - state.street[player];
+                next.street[player] += to_call;
+                next.invested[player] += to_call;
+                next.street_over = true;
+            }
+            PostflopAction::Bet(to) | PostflopAction::Raise(to) => {
+                let added = to - state.street[player];
+                next.street[player] = to;
+                next.invested[player] += added;
+                if matches!(action, PostflopAction::Raise(_)) {
+                    next.raises += 1;
+                }
+            }
+        }
+        next.to_act = 1 - player;
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+    use crate::cfr::{CfrSolver, CfrVariant};
+
+    fn board(cards: &str) -> ([Card; 3], Option<Card>, Option<Card>) {
+        let cards = parse_cards(cards).unwrap();
+        ([cards[0], cards[1], cards[2]], cards.get(3).copied(), cards.get(4).copied())
+    }
+
+    #[test]
+    fn test_river_polarised_bet() {
+        // The second player has the nuts or nothing, and the first only a bluff catcher
+        let (flop, turn, river) = board("Ks7d2c4h9s");
+        let ranges = [&Range::parse("KhQh, KdQd").unwrap(), &Range::parse("AA, 65s").unwrap()];
+        let sizes = BetSizes { bets: vec![1.0], raises: vec![], max_raises: 0 };
+        let game = PostflopGame::build(ranges, flop, turn, river, 10.0, 10.0, sizes).unwrap();
+        let mut solver = CfrSolver::new(game, CfrVariant::Plus, 0);
+        solver.train(300);
+        assert!(solver.get_exploitability() < 0.1);
+
+        let profile = solver.get_average_strategy();
+        let game = solver.game();
+        let (actions, strategies) = game.get_strategies(&profile, &[0]).unwrap();
+        assert_eq!(actions, vec![PostflopAction::Check, PostflopAction::Bet(10.0)]);
+        for strategy in &strategies {
+            assert!((strategy.probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            if strategy.hole[0].value() == 14 {
+                assert!(strategy.probabilities[1] > 0.95 && strategy.ev > 10.0);
+            } else {
+                // Bluffs make the pot-sized bet break even for the bluff catcher
+                assert!(strategy.probabilities[1] > 0.2 && strategy.probabilities[1] < 0.8);
+            }
+        }
+
+        // Facing the bet, calling and folding are close to indifferent
+        let (actions, strategies) = game.get_strategies(&profile, &[0, 1]).unwrap();
+        assert_eq!(actions, vec![PostflopAction::Fold, PostflopAction::Call]);
+        assert!(strategies.iter().all(|strategy| strategy.probabilities[1] > 0.3 && strategy.probabilities[1] < 0.7));
+        assert!(game.get_strategies(&profile, &[0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_turn_runs_out_river() {
+        let (flop, turn, _) = board("Ks7d2c4h");
+        let ranges = [&Range::parse("AhAd").unwrap(), &Range::parse("KhQh, 6h5h").unwrap()];
+        let sizes = BetSizes { bets: vec![0.5], raises: vec![1.0], max_raises: 1 };
+        let game = PostflopGame::build(ranges, flop, turn, None, 4.0, 20.0, sizes).unwrap();
+        let root = game.next(&game.root(), 0);
+        let labels: Vec<String> = game.get_actions(&root).iter().map(|action| action.to_string()).collect();
+        assert_eq!(labels, ["x", "b2", "b20"]);
+
+        let mut solver = CfrSolver::new(game, CfrVariant::Plus, 0);
+        solver.train(30);
+        assert!(solver.get_exploitability() < 0.2);
+        let (_, strategies) = solver.game().get_strategies(&solver.get_average_strategy(), &[]).unwrap();
+        assert_eq!(strategies.len(), 1);
+        // Aces are ahead of both hands, so they expect more than half the pot
+        assert!(strategies[0].ev > 2.0);
+
+        // With no raise sizes a player facing a bet can still move all-in
+        let sizes = BetSizes { bets: vec![0.5], raises: vec![], max_raises: 1 };
+        let game = PostflopGame::build(ranges, flop, turn, None, 4.0, 20.0, sizes).unwrap();
+        let facing_bet = game.next(&game.next(&game.root(), 0), 1);
+        let labels: Vec<String> = game.get_actions(&facing_bet).iter().map(|action| action.to_string()).collect();
+        assert_eq!(labels, ["f", "c", "r20"]);
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let (flop, turn, river) = board("Ks7d2c4h9s");
+        let sizes = BetSizes { bets: vec![1.0], raises: vec![], max_raises: 0 };
+        let aces = Range::parse("AA").unwrap();
+        let kings = Range::parse("KsKh").unwrap();
+        assert!(PostflopGame::build([&aces, &kings], flop, turn, river, 10.0, 10.0, sizes.clone()).is_err());
+        assert!(PostflopGame::build([&aces, &aces], flop, turn, river, 0.0, 10.0, sizes.clone()).is_err());
+        let repeated = board("Ks7d2c4hKs");
+        assert!(PostflopGame::build([&aces, &aces], repeated.0, repeated.1, repeated.2, 10.0, 10.0, sizes.clone()).is_err());
+        assert!(PostflopGame::build([&aces, &aces], flop, None, river, 10.0, 10.0, sizes.clone()).is_err());
+        let negative = BetSizes { bets: vec![-1.0], ..sizes };
+        assert!(PostflopGame::build([&aces, &aces], flop, turn, river, 10.0, 10.0, negative).is_err());
+    }
+}
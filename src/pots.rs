@@ -0,0 +1,175 @@
+//! Main and side pots. Each player who is all-in for less than the others can only win what every
+//! other player matched of their stack, so the chips put in are split into layers at each all-in
+//! amount, and each layer is won by the best hand among the players who reached it.
+
+use crate::cards::{Card, HandRank, Suit};
+
+/// Chips that can be won by any of the `eligible` seats
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pot {
+    pub amount: u64,
+    pub eligible: Vec<usize>,
+}
+
+/// Who gets the chips left over when a pot cannot be split evenly between tied winners
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OddChipRule {
+    /// One chip at a time to the tied winners in order around the table, starting from the
+    /// left of the button seat given
+    LeftOfButton(usize),
+    /// One chip at a time to the tied winners holding the highest card, with suits ranked
+    /// spades, hearts, diamonds, clubs, as in stud. Holds every seat's cards.
+    HighCardBySuit(Vec<Vec<Card>>),
+}
+
+fn get_suit_order(suit: Suit) -> u8 {
+    match suit {
+        Suit::Spades => 3,
+        Suit::Hearts => 2,
+        Suit::Diamonds => 1,
+        Suit::Clubs => 0,
+        Suit::Joker => 4,
+    }
+}
+
+impl OddChipRule {
+    /// `winners` in the order they receive odd chips
+    fn order(&self, winners: &mut [usize]) {
+        match self {
+            OddChipRule::LeftOfButton(button) => {
+                winners.sort_by_key(|&winner| (winner <= *button, winner));
+            }
+            OddChipRule::HighCardBySuit(cards) => {
+                let high_card = |winner: usize| cards.get(winner)
+                    .and_then(|cards| cards.iter().map(|card| (card.value(), get_suit_order(card.suit()))).max());
+                winners.sort_by_key(|&winner| std::cmp::Reverse(high_card(winner)));
+            }
+        }
+    }
+}
+
+/// Splits the chips each seat put in into the main pot followed by any side pots. Folded seats'
+/// chips are in the pots, but folded seats are never eligible. Chips put in above what any
+/// player still in the hand matched go to the last pot.
+pub fn build_pots(contributions: &[u64], folded: &[bool]) -> Vec<Pot> {
+    let is_live = |seat: usize| !folded.get(seat).copied().unwrap_or(false);
+    let mut levels: Vec<u64> = contributions.iter().enumerate()
+        .filter(|&(seat, &amount)| is_live(seat) && amount > 0)
+        .map(|(_, &amount)| amount)
+        .collect();
+    levels.sort_unstable();
+    levels.dedup();
+    let mut pots = Vec::new();
+    let mut previous = 0;
+    for (index, &level) in levels.iter().enumerate() {
+        let top = if index == levels.len() - 1 { u64::MAX } else { level };
+        let amount = contributions.iter().map(|&amount| amount.min(top) - amount.min(previous)).sum();
+        let eligible = (0..contributions.len()).filter(|&seat| is_live(seat) && contributions[seat] >= level).collect();
+        pots.push(Pot { amount, eligible });
+        previous = level;
+    }
+    pots
+}
+
+/// Chips each seat wins from `pots`, given each seat's hand at showdown. Seats without a hand
+/// cannot win. Tied hands split a pot, with odd chips given out by `odd_chips`.
+pub fn resolve_pots(pots: &[Pot], ranks: &[Option<HandRank>], odd_chips: &OddChipRule) -> Vec<u64> {
+    let mut payouts = vec![0; ranks.len()];
+    for pot in pots {
+        let rank = |seat: usize| ranks.get(seat).copied().flatten();
+        let Some(best) = pot.eligible.iter().filter_map(|&seat| rank(seat)).max() else {
+            continue;
+        };
+        let mut winners: Vec<usize> = pot.eligible.iter().copied().filter(|&seat| rank(seat) == Some(best)).collect();
+        odd_chips.order(&mut winners);
+        let count = winners.len() as u64;
+        for (position, &winner) in winners.iter().enumerate() {
+            payouts[winner] += pot.amount / count + u64::from((position as u64) < pot.amount % count);
+        }
+    }
+    payouts
+}
+
+/// Chips each seat wins at showdown from what each put in. Seats without a hand are treated as
+/// folded.
+pub fn get_payouts(contributions: &[u64], ranks: &[Option<HandRank>], odd_chips: &OddChipRule) -> Vec<u64> {
+    let folded: Vec<bool> = (0..contributions.len()).map(|seat| ranks.get(seat).copied().flatten().is_none()).collect();
+    resolve_pots(&build_pots(contributions, &folded), ranks, odd_chips)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{get_hand_rank, parse_cards};
+
+    fn rank(cards: &str) -> Option<HandRank> {
+        (!cards.is_empty()).then(|| get_hand_rank(&parse_cards(cards).unwrap()))
+    }
+
+    #[test]
+    fn test_build_pots() {
+        // (contributions, folded, expected pots)
+        let cases: [(&[u64], &[bool], Vec<Pot>); 4] = [
+            (&[100, 100], &[false, false], vec![Pot { amount: 200, eligible: vec![0, 1] }]),
+            (
+                &[50, 200, 200],
+                &[false, false, false],
+                vec![Pot { amount: 150, eligible: vec![0, 1, 2] }, Pot { amount: 300, eligible: vec![1, 2] }],
+            ),
+            (
+                &[30, 100, 60, 100],
+                &[false, false, true, false],
+                vec![Pot { amount: 120, eligible: vec![0, 1, 3] }, Pot { amount: 170, eligible: vec![1, 3] }],
+            ),
+            // A folded seat put in more than the short all-in, which matters only for the side pot
+            (
+                &[40, 80, 80],
+                &[false, true, false],
+                vec![Pot { amount: 120, eligible: vec![0, 2] }, Pot { amount: 80, eligible: vec![2] }],
+            ),
+        ];
+        for (contributions, folded, expected) in cases {
+            assert_eq!(build_pots(contributions, folded), expected, "{contributions:?}");
+        }
+    }
+
+    /// Contributions, hole cards with "" for folded, the odd chip rule, and the expected payouts
+    type PayoutCase = (&'static [u64], [&'static str; 3], OddChipRule, [u64; 3]);
+
+    #[test]
+    fn test_get_payouts() {
+        let board = "Ad7c5h3s2d";
+        let cases: [PayoutCase; 6] = [
+            // The kicker decides between two pairs of aces, and the folded seat's chips go too
+            (&[100, 100, 100], ["AhKs", "AsQd", ""], OddChipRule::LeftOfButton(0), [300, 0, 0]),
+            // The short stack wins the main pot and the best of the rest wins the side pot
+            (&[50, 200, 200], ["4c4d", "AhKs", "AsQd"], OddChipRule::LeftOfButton(0), [150, 300, 0]),
+            // A tie splits the main pot while the side pot goes to its own winner
+            (&[50, 200, 200], ["AcKh", "AhKs", "Ts9s"], OddChipRule::LeftOfButton(0), [75, 375, 0]),
+            // Wheels split, the odd chip going left of the button
+            (&[35, 35, 35], ["4c9d", "4hJs", "KcQh"], OddChipRule::LeftOfButton(0), [52, 53, 0]),
+            (&[35, 35, 35], ["4c9d", "4hJs", "KcQh"], OddChipRule::LeftOfButton(1), [53, 52, 0]),
+            // The jack of spades is the highest card among the winners
+            (
+                &[35, 35, 35],
+                ["4c9d", "4hJs", "KcQh"],
+                OddChipRule::HighCardBySuit(["4c9d", "4hJs", "KcQh"].map(|hole| parse_cards(hole).unwrap()).to_vec()),
+                [52, 53, 0],
+            ),
+        ];
+        for (contributions, holes, odd_chips, expected) in cases {
+            let ranks: Vec<Option<HandRank>> = holes.iter().map(|hole| rank(hole).and(rank(&format!("{hole}{board}")))).collect();
+            let payouts = get_payouts(contributions, &ranks, &odd_chips);
+            assert_eq!(payouts, expected, "{holes:?}");
+            assert_eq!(payouts.iter().sum::<u64>(), contributions.iter().sum::<u64>());
+        }
+    }
+
+    #[test]
+    fn test_left_of_button_wraps() {
+        let ranks = [rank("AhKhQhJhTh"), None, rank("AsKsQsJsTs"), None];
+        assert_eq!(get_payouts(&[3, 1, 3, 0], &ranks, &OddChipRule::LeftOfButton(2)), vec![4, 0, 3, 0]);
+        assert_eq!(get_payouts(&[3, 1, 3, 0], &ranks, &OddChipRule::LeftOfButton(3)), vec![4, 0, 3, 0]);
+        assert_eq!(get_payouts(&[3, 1, 3, 0], &ranks, &OddChipRule::LeftOfButton(0)), vec![3, 0, 4, 0]);
+    }
+}
@@ -0,0 +1,402 @@
+//! Push/fold equilibria for short stacks, where every player either folds or moves all-in when
+//! first to act, and facing an all-in either calls or folds. Strategies are found by repeatedly
+//! playing best responses against the average of the strategies so far, and are given as 13x13
+//! grids of starting hands.
+//!
+//! To keep every all-in heads-up, a player who calls an all-in is assumed to end the action, so
+//! no one behind overcalls.
+
+use std::fmt;
+use rand::rngs::StdRng;
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::SeedableRng;
+use crate::cards::{get_deck_without, Card, Variant};
+use crate::range::Range;
+
+const CLASSES: usize = 169;
+const VALUE_NAMES: [char; 13] = ['A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2'];
+
+/// Grid index of a starting hand class: pairs on the diagonal, suited hands above it and
+/// offsuit hands below
+fn get_class_index(cards: &[Card; 2]) -> usize {
+    let (high, low) = (cards[0].value().max(cards[1].value()), cards[0].value().min(cards[1].value()));
+    let (row, col) = ((14 - high) as usize, (14 - low) as usize);
+    if cards[0].suit() == cards[1].suit() { row * 13 + col } else { col * 13 + row }
+}
+
+fn get_class_name(index: usize) -> String {
+    let (row, col) = (index / 13, index % 13);
+    match row.cmp(&col) {
+        std::cmp::Ordering::Equal => format!("{}{}", VALUE_NAMES[row], VALUE_NAMES[col]),
+        std::cmp::Ordering::Less => format!("{}{}s", VALUE_NAMES[row], VALUE_NAMES[col]),
+        std::cmp::Ordering::Greater => format!("{}{}o", VALUE_NAMES[col], VALUE_NAMES[row]),
+    }
+}
+
+/// Every combination of each starting hand class, by grid index
+fn get_class_combos() -> Vec<Vec<[Card; 2]>> {
+    let mut combos = vec![Vec::new(); CLASSES];
+    for combo in Range::any().combos() {
+        combos[get_class_index(combo)].push(*combo);
+    }
+    combos
+}
+
+/// Heads-up all-in equities between every pair of starting hand classes, and how likely each
+/// class is to be dealt to an opponent given the other's cards
+#[derive(Debug, Clone)]
+pub struct PreflopEquities {
+    equities: Vec<f64>,
+    weights: Vec<f64>,
+}
+
+impl PreflopEquities {
+    /// Estimates each matchup from `samples` random deals of the two classes' combinations and
+    /// the board. The same seed always gives the same table.
+    pub fn estimate(samples: usize, seed: u64) -> Result<PreflopEquities, &'static str> {
+        if samples == 0 {
+            return Err("At least one sample is needed");
+        }
+        let combos = get_class_combos();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut equities = vec![0.5; CLASSES * CLASSES];
+        let mut weights = vec![0.0; CLASSES * CLASSES];
+        for first in 0..CLASSES {
+            for second in 0..CLASSES {
+                let pairs: Vec<([Card; 2], [Card; 2])> = combos[first].iter()
+                    .flat_map(|a| combos[second].iter().filter(|b| !b.contains(&a[0]) && !b.contains(&a[1])).map(move |b| (*a, *b)))
+                    .collect();
+                weights[first * CLASSES + second] = pairs.len() as f64 / combos[first].len() as f64;
+                if second < first || pairs.is_empty() {
+                    continue;
+                }
+                let mut total = 0.0;
+                for _ in 0..samples {
+                    let (a, b) = *pairs.choose(&mut rng).expect("There is at least one pair");
+                    let mut deck = get_deck_without(&[a[0], a[1], b[0], b[1]]);
+                    let (board, _) = deck.partial_shuffle(&mut rng, 5);
+                    let (first_rank, second_rank) = (Variant::Holdem.get_hand_rank(&a, board), Variant::Holdem.get_hand_rank(&b, board));
+                    total += match first_rank.cmp(&second_rank) {
+                        std::cmp::Ordering::Greater => 1.0,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Less => 0.0,
+                    };
+                }
+                let equity = total / samples as f64;
+                equities[first * CLASSES + second] = equity;
+                equities[second * CLASSES + first] = 1.0 - equity;
+            }
+        }
+        Ok(PreflopEquities { equities, weights })
+    }
+
+    /// How often `hand` is called, and its equity when called, against a player who continues
+    /// with each class at the given frequency
+    fn against(&self, hand: usize, frequencies: &[f64]) -> (f64, f64) {
+        let (mut total, mut continuing, mut equity) = (0.0, 0.0, 0.0);
+        for (other, frequency) in frequencies.iter().enumerate() {
+            let weight = self.weights[hand * CLASSES + other];
+            total += weight;
+            continuing += weight * frequency;
+            equity += weight * frequency * self.equities[hand * CLASSES + other];
+        }
+        if continuing == 0.0 {
+            return (0.0, 0.5);
+        }
+        (continuing / total, equity / continuing)
+    }
+}
+
+/// How often each starting hand class is played, on a 13x13 grid with aces first. Pairs are on
+/// the diagonal, suited hands above it and offsuit hands below.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandGrid {
+    frequencies: Vec<f64>,
+}
+
+impl HandGrid {
+    pub fn get_frequency(&self, row: usize, col: usize) -> f64 {
+        self.frequencies[row * 13 + col]
+    }
+
+    /// The frequency of a single class written like "AKs", "AKo", or "77", or of one of its
+    /// combos such as "AhKh"
+    pub fn get_class_frequency(&self, class: &str) -> Result<f64, &'static str> {
+        let range = Range::parse(class)?;
+        let index = range.combos().first().map(get_class_index).ok_or("Give a pair, or a suited or offsuit hand")?;
+        if range.combos().iter().any(|combo| get_class_index(combo) != index) {
+            return Err("Give a single pair, or a single suited or offsuit hand");
+        }
+        Ok(self.frequencies[index])
+    }
+
+    /// Share of all 1326 combinations played
+    pub fn get_combo_fraction(&self) -> f64 {
+        let combos = |index: usize| match (index / 13).cmp(&(index % 13)) {
+            std::cmp::Ordering::Equal => 6.0,
+            std::cmp::Ordering::Less => 4.0,
+            std::cmp::Ordering::Greater => 12.0,
+        };
+        (0..CLASSES).map(|index| combos(index) * self.frequencies[index]).sum::<f64>() / 1326.0
+    }
+
+    /// The classes played at least half the time, in range notation such as "66+, A2s+, KTo+"
+    pub fn to_range_string(&self) -> String {
+        let is_played = |row: usize, col: usize| self.get_frequency(row, col) >= 0.5;
+        let mut tokens = Vec::new();
+        // Runs of pairs, then runs of kickers for each high card, suited before offsuit
+        let pair = |index: usize| format!("{}{}", VALUE_NAMES[index], VALUE_NAMES[index]);
+        for (start, end) in get_runs((0..13).map(|index| is_played(index, index))) {
+            tokens.push(match (start, end) {
+                (start, end) if start == end => pair(start),
+                (0, end) => format!("{}+", pair(end)),
+                (start, end) => format!("{}-{}", pair(start), pair(end)),
+            });
+        }
+        for suited in [true, false] {
+            let suffix = if suited { 's' } else { 'o' };
+            for high in 0..12 {
+                let kickers = (high + 1..13).map(|low| if suited { is_played(high, low) } else { is_played(low, high) });
+                let name = |low: usize| format!("{}{}{}", VALUE_NAMES[high], VALUE_NAMES[high + 1 + low], suffix);
+                for (start, end) in get_runs(kickers) {
+                    tokens.push(match (start, end) {
+                        (start, end) if start == end => name(start),
+                        (0, end) => format!("{}+", name(end)),
+                        (start, end) => format!("{}-{}", name(start), name(end)),
+                    });
+                }
+            }
+        }
+        tokens.join(", ")
+    }
+}
+
+/// The first and last index of each run of `true`
+fn get_runs(flags: impl Iterator<Item = bool>) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for (index, flag) in flags.enumerate() {
+        match runs.last_mut() {
+            Some((_, end)) if flag && *end + 1 == index => *end = index,
+            _ if flag => runs.push((index, index)),
+            _ => (),
+        }
+    }
+    runs
+}
+
+/// The grid with each class's name where it is played at least half the time
+impl fmt::Display for HandGrid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in 0..13 {
+            let cells: Vec<String> = (0..13)
+                .map(|col| if self.get_frequency(row, col) >= 0.5 { format!("{:<3}", get_class_name(row * 13 + col)) } else { " - ".to_string() })
+                .collect();
+            writeln!(f, "{}", cells.join(" ").trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// A short-handed all-in spot. Stacks are in the order players act before the flop, ending
+/// with the small blind and big blind, so heads-up the small blind comes first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PushFoldSpot {
+    stacks: Vec<f64>,
+    small_blind: f64,
+    big_blind: f64,
+    ante: f64,
+}
+
+/// Equilibrium ranges for a spot
+#[derive(Debug, Clone, PartialEq)]
+pub struct PushFoldSolution {
+    shove: Vec<HandGrid>,
+    /// Indexed by the player moving all-in and then the player calling
+    call: Vec<Vec<HandGrid>>,
+}
+
+impl PushFoldSolution {
+    /// What the player at `position` moves all-in with when everyone before has folded
+    pub fn get_shove_range(&self, position: usize) -> Option<&HandGrid> {
+        self.shove.get(position)
+    }
+
+    /// What the player at `caller` calls with after `shover` moves all-in and everyone between
+    /// folds
+    pub fn get_call_range(&self, shover: usize, caller: usize) -> Option<&HandGrid> {
+        self.call.get(shover)?.get(caller).filter(|_| caller > shover)
+    }
+}
+
+impl PushFoldSpot {
+    pub fn build(stacks: &[f64], small_blind: f64, big_blind: f64, ante: f64) -> Result<PushFoldSpot, &'static str> {
+        if stacks.len() < 2 || stacks.len() > 10 {
+            return Err("Push/fold spots are for two to ten players");
+        }
+        if small_blind < 0.0 || big_blind <= 0.0 || ante < 0.0 {
+            return Err("Blinds and antes cannot be negative");
+        }
+        if stacks.iter().any(|stack| *stack <= ante) {
+            return Err("Every stack must cover the ante");
+        }
+        Ok(PushFoldSpot { stacks: stacks.to_vec(), small_blind, big_blind, ante })
+    }
+
+    /// The chips a player has after the ante
+    fn behind(&self, position: usize) -> f64 {
+        self.stacks[position] - self.ante
+    }
+
+    fn blind(&self, position: usize) -> f64 {
+        let count = self.stacks.len();
+        let blind = if position == count - 1 {
+            self.big_blind
+        } else if position == count - 2 {
+            self.small_blind
+        } else {
+            0.0
+        };
+        blind.min(self.behind(position))
+    }
+
+    /// `player`'s stack after folding
+    fn get_fold_stack(&self, player: usize) -> f64 {
+        self.behind(player) - self.blind(player)
+    }
+
+    /// `player`'s stack after an all-in between `shover` and `caller` with `equity`
+    fn get_called_stack(&self, shover: usize, caller: usize, player: usize, equity: f64) -> f64 {
+        let effective = self.behind(shover).min(self.behind(caller));
+        let dead: f64 = (0..self.stacks.len()).filter(|&other| other != shover && other != caller).map(|other| self.blind(other)).sum();
+        let pot = self.ante * self.stacks.len() as f64 + 2.0 * effective + dead;
+        self.behind(player) - effective + equity * pot
+    }
+
+    /// `shover`'s stack when everyone folds to the all-in
+    fn get_uncalled_stack(&self, shover: usize) -> f64 {
+        let blinds: f64 = (0..self.stacks.len()).filter(|&other| other != shover).map(|other| self.blind(other)).sum();
+        self.behind(shover) + self.ante * self.stacks.len() as f64 + blinds
+    }
+
+    /// Expected stack for moving all-in with `hand` from `shover` against the call ranges
+    fn get_shove_stack(&self, equities: &PreflopEquities, call: &[Vec<Vec<f64>>], shover: usize, hand: usize) -> f64 {
+        let mut reach = 1.0;
+        let mut stack = 0.0;
+        for (caller, frequencies) in call[shover].iter().enumerate().skip(shover + 1) {
+            let (called, equity) = equities.against(hand, frequencies);
+            stack += reach * called * self.get_called_stack(shover, caller, shover, equity);
+            reach *= 1.0 - called;
+        }
+        stack + reach * self.get_uncalled_stack(shover)
+    }
+
+    /// Finds equilibrium ranges by `iterations` rounds of best responses, each averaged into
+    /// the strategies so far
+    pub fn solve(&self, equities: &PreflopEquities, iterations: usize) -> PushFoldSolution {
+        let count = self.stacks.len();
+        let mut shove = vec![vec![1.0; CLASSES]; count];
+        let mut call = vec![vec![vec![0.5; CLASSES]; count]; count];
+        for iteration in 1..=iterations {
+            let step = 1.0 / (iteration + 1) as f64;
+            let mut best_shove = vec![vec![0.0; CLASSES]; count];
+            let mut best_call = vec![vec![vec![0.0; CLASSES]; count]; count];
+            for (shover, shoves) in best_shove.iter_mut().enumerate().take(count - 1) {
+                for hand in 0..CLASSES {
+                    let is_better = self.get_shove_stack(equities, &call, shover, hand) > self.get_fold_stack(shover);
+                    shoves[hand] = f64::from(u8::from(is_better));
+                    // Calling with the same hand, against the range moving all-in
+                    let (_, equity) = equities.against(hand, &shove[shover]);
+                    for (caller, calls) in best_call[shover].iter_mut().enumerate().skip(shover + 1) {
+                        let is_better = self.get_called_stack(shover, caller, caller, equity) > self.get_fold_stack(caller);
+                        calls[hand] = f64::from(u8::from(is_better));
+                    }
+                }
+            }
+            for (current, best) in shove.iter_mut().zip(&best_shove) {
+                for (frequency, best) in current.iter_mut().zip(best) {
+                    *frequency += step * (best - *frequency);
+                }
+            }
+            for (current, best) in call.iter_mut().flatten().zip(best_call.iter().flatten()) {
+                for (frequency, best) in current.iter_mut().zip(best) {
+                    *frequency += step * (best - *frequency);
+                }
+            }
+        }
+        // The big blind never moves all-in, having no one left to fold to it
+        shove[count - 1] = vec![0.0; CLASSES];
+        PushFoldSolution {
+            shove: shove.into_iter().map(|frequencies| HandGrid { frequencies }).collect(),
+            call: call.into_iter()
+                .enumerate()
+                .map(|(shover, callers)| callers.into_iter()
+                    .enumerate()
+                    .map(|(caller, frequencies)| HandGrid { frequencies: if caller > shover { frequencies } else { vec![0.0; CLASSES] } })
+                    .collect())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hand_grid() {
+        let mut frequencies = vec![0.0; CLASSES];
+        for class in ["AA", "KK", "QQ", "88", "77", "AKs", "AQs", "AJs", "A5s", "AKo", "KQo"] {
+            let combo = Range::parse(class).unwrap().combos()[0];
+            frequencies[get_class_index(&combo)] = 1.0;
+        }
+        let grid = HandGrid { frequencies };
+        assert_eq!(grid.to_range_string(), "QQ+, 88-77, AJs+, A5s, AKo, KQo");
+        assert_eq!(Range::parse(&grid.to_range_string()).unwrap().len(), 5 * 6 + 4 * 4 + 2 * 12);
+        assert_eq!(grid.get_class_frequency("AKs"), Ok(1.0));
+        assert_eq!(grid.get_class_frequency("KQs"), Ok(0.0));
+        assert!(grid.get_class_frequency("AK").is_err());
+        for several in ["A2s+", "22-55", "AK, QQ", "AKs, AKo"] {
+            assert!(grid.get_class_frequency(several).is_err(), "{several}");
+        }
+        assert_eq!(grid.get_class_frequency("AhKh"), Ok(1.0));
+        assert!((grid.get_combo_fraction() - 70.0 / 1326.0).abs() < 1e-9);
+        let lines: Vec<String> = grid.to_string().lines().map(|line| line.to_string()).collect();
+        assert_eq!(lines.len(), 13);
+        assert!(lines[0].starts_with("AA  AKs AQs AJs"));
+        assert!(lines[1].starts_with("AKo KK   -  "));
+    }
+
+    #[test]
+    fn test_heads_up_equilibrium() {
+        let equities = PreflopEquities::estimate(12, 1).unwrap();
+        let spot = PushFoldSpot::build(&[10.0, 10.0], 0.5, 1.0, 0.0).unwrap();
+        let solution = spot.solve(&equities, 200);
+        let (shove, call) = (solution.get_shove_range(0).unwrap(), solution.get_call_range(0, 1).unwrap());
+        // At ten big blinds the small blind moves in with over half its hands, and the big
+        // blind calls with around a third
+        assert!((0.45..0.7).contains(&shove.get_combo_fraction()), "{}", shove.get_combo_fraction());
+        assert!((0.25..0.45).contains(&call.get_combo_fraction()), "{}", call.get_combo_fraction());
+        assert!(shove.get_class_frequency("AA").unwrap() > 0.99 && shove.get_class_frequency("72o").unwrap() < 0.01);
+        assert!(call.get_class_frequency("A2o").unwrap() > 0.99 && call.get_class_frequency("T2o").unwrap() < 0.01);
+        assert!(Range::parse(&shove.to_range_string()).is_ok());
+        assert!(solution.get_call_range(1, 0).is_none());
+
+        // Deeper stacks shove less
+        let deeper = PushFoldSpot::build(&[20.0, 20.0], 0.5, 1.0, 0.0).unwrap().solve(&equities, 200);
+        assert!(deeper.get_shove_range(0).unwrap().get_combo_fraction() < shove.get_combo_fraction());
+    }
+
+    #[test]
+    fn test_three_handed() {
+        let equities = PreflopEquities::estimate(12, 2).unwrap();
+        let spot = PushFoldSpot::build(&[8.0, 8.0, 8.0], 0.5, 1.0, 0.1).unwrap();
+        let solution = spot.solve(&equities, 100);
+        // The button has two players left to wake up with a hand, the small blind only one
+        let (button, small_blind) = (solution.get_shove_range(0).unwrap(), solution.get_shove_range(1).unwrap());
+        assert!(button.get_combo_fraction() < small_blind.get_combo_fraction());
+        assert_eq!(solution.get_shove_range(2).unwrap().get_combo_fraction(), 0.0);
+        assert!(solution.get_call_range(0, 2).is_some());
+        assert!(PushFoldSpot::build(&[8.0], 0.5, 1.0, 0.0).is_err());
+        assert!(PreflopEquities::estimate(0, 0).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+//! Python bindings, built with the `python` feature. Results are returned as plain Python dicts
+//! and lists so they can go straight into notebooks and data frames.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use crate::cards::{self, get_hand_rank, parse_cards, Equity, Hand, HandRank};
+use crate::range::Range;
+
+fn to_py_err(message: &'static str) -> PyErr {
+    PyValueError::new_err(message)
+}
+
+fn parse(cards: &str) -> PyResult<Vec<cards::Card>> {
+    parse_cards(cards).map_err(to_py_err)
+}
+
+fn hand_name(hand: &Hand) -> &'static str {
+    match hand {
+        Hand::HighCard(_) => "HighCard",
+        Hand::Pair(_) => "Pair",
+        Hand::TwoPair(_, _) => "TwoPair",
+        Hand::ThreeOfAKind(_) => "ThreeOfAKind",
+        Hand::Straight(_) => "Straight",
+        Hand::Flush(_) => "Flush",
+        Hand::FullHouse(_, _) => "FullHouse",
+        Hand::FourOfAKind(_) => "FourOfAKind",
+        Hand::StraightFlush(_) => "StraightFlush",
+        Hand::RoyalFlush => "RoyalFlush",
+        Hand::FiveOfAKind(_) => "FiveOfAKind",
+    }
+}
+
+fn rank_to_dict<'py>(py: Python<'py>, rank: HandRank) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("hand", hand_name(&rank.hand()))?;
+    let values: Vec<u32> = rank.values().into_iter().map(u32::from).collect();
+    dict.set_item("values", values)?;
+    dict.set_item("rank", rank.as_u32())?;
+    Ok(dict)
+}
+
+fn equity_to_dict(py: Python<'_>, equity: Equity) -> PyResult<Bound<'_, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("win", equity.win)?;
+    dict.set_item("tie", equity.tie)?;
+    dict.set_item("equity", equity.equity)?;
+    Ok(dict)
+}
+
+#[pyclass(name = "Card", frozen, eq)]
+#[derive(Clone, PartialEq)]
+struct PyCard(cards::Card);
+
+#[pymethods]
+impl PyCard {
+    #[new]
+    fn new(card: &str) -> PyResult<Self> {
+        card.parse().map(PyCard).map_err(to_py_err)
+    }
+
+    #[getter]
+    fn value(&self) -> u8 {
+        self.0.value()
+    }
+
+    #[getter]
+    fn suit(&self) -> String {
+        format!("{:?}", self.0.suit())
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Card('{}')", self.0)
+    }
+}
+
+#[pyclass(name = "Game", frozen)]
+struct PyGame(cards::Game);
+
+#[pymethods]
+impl PyGame {
+    /// Game("AhKd", "Qs7c2d") with two hole cards and three to five board cards
+    #[new]
+    fn new(hole: &str, board: &str) -> PyResult<Self> {
+        let (hole, board) = (parse(hole)?, parse(board)?);
+        if hole.len() != 2 || !(3..=5).contains(&board.len()) {
+            return Err(PyValueError::new_err("A game needs two hole cards and three to five board cards"));
+        }
+        cards::Game::build([hole[0], hole[1]], [board[0], board[1], board[2]], board.get(3).copied(), board.get(4).copied())
+            .map(PyGame)
+            .map_err(to_py_err)
+    }
+
+    #[getter]
+    fn hole(&self) -> Vec<PyCard> {
+        self.0.hole().iter().copied().map(PyCard).collect()
+    }
+
+    #[getter]
+    fn board(&self) -> Vec<PyCard> {
+        self.0.board().into_iter().map(PyCard).collect()
+    }
+
+    /// Equity against each opponent's known hole cards, with this game's hand first
+    fn equity<'py>(&self, py: Python<'py>, opponents: Vec<String>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let holes = opponents.iter().map(|opponent| parse(opponent)).collect::<PyResult<Vec<_>>>()?;
+        let equities = py.allow_threads(|| self.0.get_equity(&holes)).map_err(to_py_err)?;
+        equities.into_iter().map(|equity| equity_to_dict(py, equity)).collect()
+    }
+
+    /// Equity against a single opponent holding any hand in the range
+    fn equity_vs_range<'py>(&self, py: Python<'py>, range: &str) -> PyResult<Bound<'py, PyDict>> {
+        let range = Range::parse(range).map_err(to_py_err)?;
+        let equity = py.allow_threads(|| self.0.get_equity_against_range(&range)).map_err(to_py_err)?;
+        equity_to_dict(py, equity)
+    }
+}
+
+/// Ranks the best five cards among five to seven cards, such as evaluate("AhKhQhJhTh9c")
+#[pyfunction]
+fn evaluate<'py>(py: Python<'py>, cards: &str) -> PyResult<Bound<'py, PyDict>> {
+    let cards = parse(cards)?;
+    if !(5..=7).contains(&cards.len()) {
+        return Err(PyValueError::new_err("A hand must have between five and seven cards"));
+    }
+    if cards.iter().enumerate().any(|(index, card)| cards[..index].contains(card)) {
+        return Err(PyValueError::new_err("The same card cannot be used twice"));
+    }
+    rank_to_dict(py, get_hand_rank(&cards))
+}
+
+/// Expands a range such as "QQ+,AKs" into its combinations, such as ["QsQd", ...]
+#[pyfunction]
+fn parse_range(range: &str) -> PyResult<Vec<String>> {
+    let range = Range::parse(range).map_err(to_py_err)?;
+    Ok(range.combos().iter().map(|[a, b]| format!("{a}{b}")).collect())
+}
+
+/// Equity of each hand over every runout of the board, such as equity(["AhAd", "KsKc"], "2h7d9c")
+#[pyfunction]
+#[pyo3(signature = (hands, board = "", dead = ""))]
+fn equity<'py>(py: Python<'py>, hands: Vec<String>, board: &str, dead: &str) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    let hands = hands.iter().map(|hand| parse(hand)).collect::<PyResult<Vec<_>>>()?;
+    let (board, dead) = (parse(board)?, parse(dead)?);
+    let equities = py.allow_threads(|| cards::get_equity(&hands, &board, &dead)).map_err(to_py_err)?;
+    equities.into_iter().map(|equity| equity_to_dict(py, equity)).collect()
+}
+
+#[pymodule]
+fn poker_odds(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyCard>()?;
+    module.add_class::<PyGame>()?;
+    module.add_function(wrap_pyfunction!(evaluate, module)?)?;
+    module.add_function(wrap_pyfunction!(parse_range, module)?)?;
+    module.add_function(wrap_pyfunction!(equity, module)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::ffi::c_str;
+
+    /// Runs Python `code` with the module imported as `poker_odds`
+    fn run(code: &std::ffi::CStr) -> PyResult<()> {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "poker_odds")?;
+            poker_odds(&module)?;
+            let globals = PyDict::new(py);
+            globals.set_item("poker_odds", module)?;
+            py.run(code, Some(&globals), None)
+        })
+    }
+
+    #[test]
+    fn test_functions() {
+        run(c_str!(r#"
+rank = poker_odds.evaluate("AhKhQhJhTh9c")
+assert rank == {"hand": "RoyalFlush", "values": [14], "rank": rank["rank"]}, rank
+assert isinstance(rank["rank"], int)
+pair = poker_odds.evaluate("AhAd7c5s2h")
+assert pair["hand"] == "Pair" and pair["values"] == [14, 7, 5, 2], pair
+assert pair["rank"] < rank["rank"]
+
+combos = poker_odds.parse_range("QQ+, AKs")
+assert len(combos) == 22 and all(isinstance(combo, str) and len(combo) == 4 for combo in combos)
+
+equities = poker_odds.equity(["AhAd", "KsKc"], board="2h7d9c")
+assert [sorted(equity) for equity in equities] == [["equity", "tie", "win"]] * 2
+assert abs(sum(equity["equity"] for equity in equities) - 1.0) < 1e-9
+assert poker_odds.equity(["AhAd", "KsKc"], "2h7d9c", "Kd") != equities
+"#)).unwrap();
+    }
+
+    #[test]
+    fn test_classes() {
+        run(c_str!(r#"
+card = poker_odds.Card("Ah")
+assert (card.value, card.suit, str(card), repr(card)) == (14, "Hearts", "Ah", "Card('Ah')")
+assert card == poker_odds.Card("Ah") and card != poker_odds.Card("Ad")
+
+game = poker_odds.Game("AhKd", "Qs7c2dJs")
+assert [str(card) for card in game.hole] == ["Ah", "Kd"]
+assert [str(card) for card in game.board] == ["Qs", "7c", "2d", "Js"]
+equities = game.equity(["QhQd"])
+assert len(equities) == 2 and equities[0]["equity"] < 0.2
+assert 0.0 < game.equity_vs_range("QQ, TT")["equity"] < 1.0
+"#)).unwrap();
+    }
+
+    #[test]
+    fn test_errors_become_value_errors() {
+        run(c_str!(r#"
+calls = [
+    lambda: poker_odds.Card("Ax"),
+    lambda: poker_odds.Game("AhKd", "Qs"),
+    lambda: poker_odds.Game("AhAh", "Qs7c2d"),
+    lambda: poker_odds.evaluate("AhKh"),
+    lambda: poker_odds.evaluate("AhAhAhKdKd"),
+    lambda: poker_odds.parse_range("ZZ"),
+    lambda: poker_odds.equity(["AhAd", "AhKc"]),
+    lambda: poker_odds.Game("AhKd", "Qs7c2d").equity(["Ah2c"]),
+    lambda: poker_odds.Game("AhKd", "Qs7c2d").equity_vs_range("AhKd"),
+]
+for call in calls:
+    try:
+        call()
+    except ValueError as error:
+        assert str(error), error
+    else:
+        raise AssertionError("expected a ValueError")
+"#)).unwrap();
+    }
+}
@@ -0,0 +1,235 @@
+use std::str::FromStr;
+use crate::cards::{get_deck_without, parse_cards, Card, SUITS};
+
+/// The hole card combinations an opponent could be holding
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    combos: Vec<[Card; 2]>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Suitedness {
+    Suited,
+    Offsuit,
+    Any,
+}
+
+/// A starting hand class such as "AKs" or "77", with the higher value first
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct HandClass {
+    high: u8,
+    low: u8,
+    suitedness: Suitedness,
+}
+
+fn parse_value(c: char) -> Option<u8> {
+    match c.to_ascii_uppercase() {
+        'T' => Some(10),
+        'J' => Some(11),
+        'Q' => Some(12),
+        'K' => Some(13),
+        'A' => Some(14),
+        digit @ '2'..='9' => Some(digit as u8 - b'0'),
+        _ => None,
+    }
+}
+
+fn parse_hand_class(s: &str) -> Result<HandClass, &'static str> {
+    let chars: Vec<char> = s.chars().collect();
+    if chars.len() != 2 && chars.len() != 3 {
+        return Err("Hand class must be two values and an optional s or o, such as AKs");
+    }
+    let (Some(first), Some(second)) = (parse_value(chars[0]), parse_value(chars[1])) else {
+        return Err("Hand class values must be one of 23456789TJQKA");
+    };
+    let suitedness = match chars.get(2).map(|c| c.to_ascii_lowercase()) {
+        None => Suitedness::Any,
+        Some('s') => Suitedness::Suited,
+        Some('o') => Suitedness::Offsuit,
+        Some(_) => return Err("Hand class must end in s (suited) or o (offsuit)"),
+    };
+    if first == second && suitedness != Suitedness::Any {
+        return Err("Pairs cannot be suited or offsuit");
+    }
+    Ok(HandClass { high: first.max(second), low: first.min(second), suitedness })
+}
+
+impl HandClass {
+    fn is_pair(&self) -> bool {
+        self.high == self.low
+    }
+
+    fn combos(&self) -> Vec<[Card; 2]> {
+        let mut combos = Vec::new();
+        for (i, high_suit) in SUITS.into_iter().enumerate() {
+            for (j, low_suit) in SUITS.into_iter().enumerate() {
+                let include = if self.is_pair() {
+                    i < j
+                } else {
+                    match self.suitedness {
+                        Suitedness::Suited => i == j,
+                        Suitedness::Offsuit => i != j,
+                        Suitedness::Any => true,
+                    }
+                };
+                if include {
+                    combos.push([
+                        Card::build(high_suit, self.high).unwrap(),
+                        Card::build(low_suit, self.low).unwrap(),
+                    ]);
+                }
+            }
+        }
+        combos
+    }
+}
+
+fn parse_token(token: &str) -> Result<Vec<[Card; 2]>, &'static str> {
+    if token.len() == 4 {
+        if let Ok(cards) = parse_cards(token) {
+            if cards[0] == cards[1] {
+                return Err("A combination cannot use the same card twice");
+            }
+            return Ok(vec![[cards[0], cards[1]]]);
+        }
+    }
+    let classes: Vec<HandClass> = if let Some((from, to)) = token.split_once('-') {
+        let (from, to) = (parse_hand_class(from)?, parse_hand_class(to)?);
+        if from.suitedness != to.suitedness || from.is_pair() != to.is_pair() {
+            return Err("Both ends of a dash range must be the same kind of hand");
+        }
+        if from.is_pair() {
+            (from.low.min(to.low)..=from.low.max(to.low))
+                .map(|value| HandClass { high: value, low: value, suitedness: from.suitedness })
+                .collect()
+        } else if from.high == to.high {
+            (from.low.min(to.low)..=from.low.max(to.low))
+                .map(|low| HandClass { high: from.high, low, suitedness: from.suitedness })
+                .collect()
+        } else {
+            return Err("Both ends of a dash range must share their high card");
+        }
+    } else if let Some(class) = token.strip_suffix('+') {
+        let class = parse_hand_class(class)?;
+        if class.is_pair() {
+            (class.low..=14).map(|value| HandClass { high: value, low: value, ..class }).collect()
+        } else {
+            (class.low..class.high).map(|low| HandClass { low, ..class }).collect()
+        }
+    } else {
+        vec![parse_hand_class(token)?]
+    };
+    Ok(classes.iter().flat_map(|class| class.combos()).collect())
+}
+
+impl Range {
+    /// Parses a comma separated list of hands in the usual notation: pairs ("77"), suited or
+    /// offsuit hands ("AKs", "AKo", or "AK" for both), a trailing "+" to include every better
+    /// kicker ("ATs+", "22+"), dash ranges ("A2s-A5s", "22-55"), and exact combinations ("AhKh")
+    pub fn parse(s: &str) -> Result<Range, &'static str> {
+        let mut combos = Vec::new();
+        for token in s.split(',').map(|token| token.trim()) {
+            if token.is_empty() {
+                continue;
+            }
+            combos.extend(parse_token(token)?);
+        }
+        if combos.is_empty() {
+            return Err("Range must contain at least one hand");
+        }
+        Ok(Range::from_combos(combos))
+    }
+
+    /// Every possible pair of hole cards
+    pub fn any() -> Range {
+        let deck = get_deck_without(&[]);
+        let mut combos = Vec::new();
+        for (index, first) in deck.iter().enumerate() {
+            for second in &deck[index + 1..] {
+                combos.push([*first, *second]);
+            }
+        }
+        Range { combos }
+    }
+
+    /// Builds a range from exact combinations, ignoring duplicates
+    pub fn from_combos(combos: Vec<[Card; 2]>) -> Range {
+        let mut unique: Vec<[Card; 2]> = Vec::new();
+        for combo in combos {
+            if !unique.iter().any(|[a, b]| (*a == combo[0] && *b == combo[1]) || (*a == combo[1] && *b == combo[0])) {
+                unique.push(combo);
+            }
+        }
+        Range { combos: unique }
+    }
+
+    pub fn combos(&self) -> &[[Card; 2]] {
+        &self.combos
+    }
+
+    /// The combinations that do not contain any of `used_cards`
+    pub fn combos_without(&self, used_cards: &[Card]) -> Vec<[Card; 2]> {
+        self.combos.iter()
+            .filter(|combo| !used_cards.contains(&combo[0]) && !used_cards.contains(&combo[1]))
+            .copied()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.combos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.combos.is_empty()
+    }
+}
+
+impl FromStr for Range {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Range, Self::Err> {
+        Range::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hand_classes() {
+        assert_eq!(Range::parse("77").unwrap().len(), 6);
+        assert_eq!(Range::parse("AKs").unwrap().len(), 4);
+        assert_eq!(Range::parse("AKo").unwrap().len(), 12);
+        assert_eq!(Range::parse("KA").unwrap().len(), 16);
+        let cards = parse_cards("AhKh").unwrap();
+        assert_eq!(Range::parse("AhKh").unwrap().combos(), &[[cards[0], cards[1]]]);
+    }
+
+    #[test]
+    fn test_parse_plus_and_dash() {
+        assert_eq!(Range::parse("TT+").unwrap().len(), 5 * 6);
+        assert_eq!(Range::parse("A2s+").unwrap().len(), 12 * 4);
+        assert_eq!(Range::parse("KTo+").unwrap().len(), 3 * 12);
+        assert_eq!(Range::parse("22-55").unwrap().len(), 4 * 6);
+        assert_eq!(Range::parse("A5s-A2s").unwrap().len(), 4 * 4);
+        assert_eq!(Range::parse("QQ+, AK, AKs").unwrap().len(), 3 * 6 + 16);
+        assert_eq!(Range::any().len(), 1326);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for range in ["", "AAs", "A", "AK+s", "AKx", "22-AKs", "A2s-K2s", "AhAh", "1h2h"] {
+            if Range::parse(range).is_ok() {
+                panic!("{range} is not a valid range");
+            }
+        }
+    }
+
+    #[test]
+    fn test_combos_without() {
+        let range = Range::parse("AA").unwrap();
+        let used_cards = parse_cards("AhKd").unwrap();
+        assert_eq!(range.combos_without(&used_cards).len(), 3);
+    }
+}
@@ -0,0 +1,86 @@
+use itertools::Itertools;
+use crate::cards::{get_hand_rank, Card, HandRank};
+
+/// Place of each standard category (high card up to straight flush) in the short-deck ordering,
+/// where a flush beats a full house
+fn get_short_deck_order(category: u32, trips_beat_straight: bool) -> u32 {
+    match category {
+        3 if trips_beat_straight => 4,
+        4 if trips_beat_straight => 3,
+        5 => 6,
+        6 => 5,
+        category => category,
+    }
+}
+
+/// Ranks exactly five cards, counting A-6-7-8-9 as a nine-high straight
+fn get_five_card_rank(cards: &[Card]) -> HandRank {
+    let mut values: Vec<u8> = cards.iter().map(|card| card.value()).collect();
+    values.sort();
+    if values == [6, 7, 8, 9, 14] {
+        // Swap the ace for a five so the usual evaluator sees a nine-high straight
+        let low_cards: Vec<Card> = cards.iter()
+            .map(|card| if card.value() == 14 { Card::build(card.suit(), 5).unwrap() } else { *card })
+            .collect();
+        return get_hand_rank(&low_cards);
+    }
+    get_hand_rank(cards)
+}
+
+/// Ranks the best short-deck hand among five or more cards
+pub fn get_short_deck_hand_rank(cards: &[Card], trips_beat_straight: bool) -> HandRank {
+    cards.iter()
+        .copied()
+        .combinations(5)
+        .map(|hand| {
+            let rank = get_five_card_rank(&hand);
+            rank.with_order(get_short_deck_order(rank.category(), trips_beat_straight))
+        })
+        .max()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{parse_cards, Game, Hand, Variant};
+
+    fn rank(cards: &str) -> HandRank {
+        get_short_deck_hand_rank(&parse_cards(cards).unwrap(), false)
+    }
+
+    #[test]
+    fn test_short_deck_hand_rank() {
+        assert_eq!(rank("Ah6d7c8s9h").hand(), Hand::Straight(9));
+        assert_eq!(rank("Ah6h7h8h9h").hand(), Hand::StraightFlush(9));
+        assert!(rank("Ah6d7c8s9h") < rank("6d7c8s9hTd"));
+        // A flush beats a full house, even when both are in the same seven cards
+        assert_eq!(rank("KhKdKc9h7h6hJh").hand(), Hand::Flush(13));
+        assert!(rank("Ah9h8h7hJh") > rank("KhKdKc9s9d"));
+        assert!(rank("KhKdKcQsJd") < rank("6d7c8s9hTd"));
+
+        let trips = get_short_deck_hand_rank(&parse_cards("KhKdKcQsJd").unwrap(), true);
+        let straight = get_short_deck_hand_rank(&parse_cards("6d7c8s9hTd").unwrap(), true);
+        assert!(trips > straight);
+    }
+
+    #[test]
+    fn test_short_deck_equity() {
+        let variant = Variant::ShortDeck { trips_beat_straight: false };
+        assert_eq!(variant.deck().len(), 36);
+        let cards = parse_cards("AhAsKhQh6c7d8s").unwrap();
+        assert!(Game::build_variant(variant, &parse_cards("Ah2s").unwrap(), [cards[2], cards[3], cards[4]], None, None).is_err());
+
+        // The board plays a flush for the first hand over the second's full house
+        let game = Game::build_variant(variant, &cards[0..2], [cards[2], cards[3], cards[4]], None, None).unwrap();
+        let board = parse_cards("Kh7h6hKd6c").unwrap();
+        let equity = variant.get_equity(&[parse_cards("AhJh").unwrap(), parse_cards("KcTd").unwrap()], &board, &[]).unwrap();
+        assert_eq!(equity[0].win, 1.0);
+        let equity = Variant::Holdem.get_equity(&[parse_cards("AhJh").unwrap(), parse_cards("KcTd").unwrap()], &board, &[]).unwrap();
+        assert_eq!(equity[1].win, 1.0);
+
+        // Runouts only come from the 36 card deck
+        let equity = game.get_equity(&[parse_cards("9c9d").unwrap()]).unwrap();
+        assert!((equity[0].equity + equity[1].equity - 1.0).abs() < 1e-9);
+    }
+}
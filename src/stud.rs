@@ -0,0 +1,299 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use crate::cards::{count_deals, deal_from, for_each_deal, get_deck_without, get_hand_rank, split_between, Card, Equity, EquityTotals, HandRank, MAX_EXACT_DEALS};
+use crate::low::{get_ace_to_five_low, LowHand};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Street {
+    Third,
+    Fourth,
+    Fifth,
+    Sixth,
+    Seventh,
+}
+
+/// One player's cards in Seven Card Stud. Two down cards and one up card are dealt on third
+/// street, one more up card on each of fourth through sixth, and a final down card on seventh.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StudHand {
+    down: Vec<Card>,
+    up: Vec<Card>,
+}
+
+impl StudHand {
+    pub fn build(down: Vec<Card>, up: Vec<Card>) -> Result<StudHand, &'static str> {
+        match (down.len(), up.len()) {
+            (2, 1..=4) | (3, 4) => Ok(StudHand { down, up }),
+            _ => Err("Stud hands have two down cards and one to four up cards, then a third down card on seventh street"),
+        }
+    }
+
+    pub fn down(&self) -> &[Card] {
+        &self.down
+    }
+
+    pub fn up(&self) -> &[Card] {
+        &self.up
+    }
+
+    pub fn street(&self) -> Street {
+        match (self.down.len(), self.up.len()) {
+            (2, 1) => Street::Third,
+            (2, 2) => Street::Fourth,
+            (2, 3) => Street::Fifth,
+            (2, 4) => Street::Sixth,
+            _ => Street::Seventh,
+        }
+    }
+
+    pub fn cards(&self) -> Vec<Card> {
+        let mut cards = self.down.clone();
+        cards.extend(&self.up);
+        cards
+    }
+}
+
+/// Games dealt with the Seven Card Stud structure
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum StudVariant {
+    /// The best high hand wins
+    SevenCardStud,
+    /// The best ace-to-five low wins
+    Razz,
+}
+
+/// A stud pot between the players still in the hand. Up cards of players who have folded are
+/// dead and cannot be dealt to anyone.
+pub struct StudGame {
+    variant: StudVariant,
+    players: Vec<StudHand>,
+    dead_cards: Vec<Card>,
+}
+
+impl StudGame {
+    pub fn build(players: Vec<StudHand>, dead_cards: Vec<Card>) -> Result<StudGame, &'static str> {
+        StudGame::build_variant(StudVariant::SevenCardStud, players, dead_cards)
+    }
+
+    pub fn build_variant(variant: StudVariant, players: Vec<StudHand>, dead_cards: Vec<Card>) -> Result<StudGame, &'static str> {
+        if players.len() < 2 {
+            return Err("At least two players are needed");
+        }
+        if players.iter().any(|player| player.street() != players[0].street()) {
+            return Err("Every player must be on the same street");
+        }
+        let game = StudGame { variant, players, dead_cards };
+        let used_cards = game.get_used_cards();
+        if used_cards.iter().enumerate().any(|(index, card)| used_cards[..index].contains(card)) {
+            return Err("The same card cannot be used twice");
+        }
+        if used_cards.len() + game.get_cards_to_come() * game.players.len() > 52 {
+            return Err("Too many players for the deck to deal every card");
+        }
+        Ok(game)
+    }
+
+    pub fn variant(&self) -> StudVariant {
+        self.variant
+    }
+
+    pub fn players(&self) -> &[StudHand] {
+        &self.players
+    }
+
+    pub fn dead_cards(&self) -> &[Card] {
+        &self.dead_cards
+    }
+
+    pub fn street(&self) -> Street {
+        self.players[0].street()
+    }
+
+    /// Folds a player, whose up cards become dead. Their down cards were never seen, so they
+    /// stay in the unknown part of the deck.
+    pub fn fold(&mut self, player: usize) -> Result<(), &'static str> {
+        if player >= self.players.len() {
+            return Err("No such player");
+        }
+        if self.players.len() == 2 {
+            return Err("At least two players must stay in the hand");
+        }
+        let folded = self.players.remove(player);
+        self.dead_cards.extend(folded.up);
+        Ok(())
+    }
+
+    /// Deals the next street to every player, in seat order
+    pub fn deal(&mut self, cards: &[Card]) -> Result<(), &'static str> {
+        if self.street() == Street::Seventh {
+            return Err("Every street has already been dealt");
+        }
+        if cards.len() != self.players.len() {
+            return Err("Each player must be dealt exactly one card");
+        }
+        let used_cards = self.get_used_cards();
+        if cards.iter().enumerate().any(|(index, card)| used_cards.contains(card) || cards[..index].contains(card)) {
+            return Err("The same card cannot be used twice");
+        }
+        for (player, card) in self.players.iter_mut().zip(cards) {
+            if player.street() == Street::Sixth {
+                player.down.push(*card);
+            } else {
+                player.up.push(*card);
+            }
+        }
+        Ok(())
+    }
+
+    fn get_used_cards(&self) -> Vec<Card> {
+        let mut used_cards: Vec<Card> = self.players.iter().flat_map(|player| player.cards()).collect();
+        used_cards.extend(&self.dead_cards);
+        used_cards
+    }
+
+    fn get_cards_to_come(&self) -> usize {
+        7 - self.players[0].cards().len()
+    }
+
+    fn get_shares(&self, final_cards: &[Vec<Card>]) -> Vec<f64> {
+        let hands = self.players.iter().zip(final_cards).map(|(player, to_come)| {
+            let mut cards = player.cards();
+            cards.extend(to_come);
+            cards
+        });
+        match self.variant {
+            StudVariant::SevenCardStud => split_between(&hands.map(|cards| get_hand_rank(&cards)).collect::<Vec<HandRank>>()),
+            StudVariant::Razz => split_between(&hands.map(|cards| get_ace_to_five_low(&cards)).collect::<Vec<LowHand>>()),
+        }
+    }
+
+    fn get_counts(&self) -> Vec<usize> {
+        vec![self.get_cards_to_come(); self.players.len()]
+    }
+
+    /// Calculates the equity of every live hand by enumerating every way the remaining cards can
+    /// be dealt. Earlier streets have too many deals, so this is refused above a million of them;
+    /// use `estimate_equity` instead.
+    pub fn get_equity(&self) -> Result<Vec<Equity>, &'static str> {
+        let unused_cards = get_deck_without(&self.get_used_cards());
+        if count_deals(unused_cards.len(), &self.get_counts()) > MAX_EXACT_DEALS {
+            return Err("Too many deals for an exact calculation; use estimate_equity instead");
+        }
+        let mut totals = EquityTotals::new(self.players.len());
+        for_each_deal(&unused_cards, &self.get_counts(), &mut |final_cards| totals.add(&self.get_shares(final_cards)));
+        Ok(totals.into_equity())
+    }
+
+    /// Estimates the equity of every live hand from `trials` random deals of the remaining cards.
+    /// The same seed always gives the same estimate.
+    pub fn estimate_equity(&self, trials: usize, seed: u64) -> Result<Vec<Equity>, &'static str> {
+        if trials == 0 {
+            return Err("At least one trial is needed");
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut unused_cards = get_deck_without(&self.get_used_cards());
+        let counts = self.get_counts();
+        let mut totals = EquityTotals::new(self.players.len());
+        for _ in 0..trials {
+            unused_cards.shuffle(&mut rng);
+            totals.add(&self.get_shares(&deal_from(&unused_cards, &counts)));
+        }
+        Ok(totals.into_equity())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_cards;
+
+    fn hand(down: &str, up: &str) -> StudHand {
+        StudHand::build(parse_cards(down).unwrap(), parse_cards(up).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_build_stud_hand() {
+        assert_eq!(hand("AhAd", "Kc").street(), Street::Third);
+        assert_eq!(hand("AhAd", "KcQcJc2d").street(), Street::Sixth);
+        assert_eq!(hand("AhAd3s", "KcQcJc2d").street(), Street::Seventh);
+        assert!(StudHand::build(parse_cards("AhAd3s").unwrap(), parse_cards("Kc").unwrap()).is_err());
+        assert!(StudHand::build(parse_cards("Ah").unwrap(), parse_cards("KcQc").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_build_stud_game() {
+        assert!(StudGame::build(vec![hand("AhAd", "Kc"), hand("AhQd", "Kd")], vec![]).is_err());
+        assert!(StudGame::build(vec![hand("AhAd", "Kc"), hand("QhQd", "KdTs")], vec![]).is_err());
+        assert!(StudGame::build(vec![hand("AhAd", "Kc")], vec![]).is_err());
+        assert!(StudGame::build(vec![hand("AhAd", "Kc"), hand("QhQd", "Kd")], parse_cards("Ah").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_deal_and_fold() {
+        let mut game = StudGame::build(vec![hand("AhAd", "Kc"), hand("QhQd", "Kd"), hand("2c7d", "9s")], vec![]).unwrap();
+        game.fold(2).unwrap();
+        assert_eq!(game.dead_cards(), parse_cards("9s").unwrap());
+        assert!(game.fold(0).is_err());
+        assert!(game.deal(&parse_cards("9s8s").unwrap()).is_err());
+        for street in ["2s3s", "4s5s", "6s7s", "8s8h"] {
+            game.deal(&parse_cards(street).unwrap()).unwrap();
+        }
+        assert_eq!(game.street(), Street::Seventh);
+        assert_eq!(game.players()[1].down(), parse_cards("QhQd8h").unwrap());
+        assert!(game.deal(&parse_cards("JsJh").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_stud_equity() {
+        // On seventh street the result is known
+        let game = StudGame::build(vec![hand("AhAd2c", "Kc7s8d9h"), hand("QhQd3c", "Ks7h8s9d")], vec![]).unwrap();
+        let equity = game.get_equity().unwrap();
+        assert_eq!(equity[0].win, 1.0);
+
+        // Sixth street: one card each to come
+        let game = StudGame::build(vec![hand("AhAd", "Kc7s8d2h"), hand("QhQd", "Ks7h8s3d")], vec![]).unwrap();
+        let equity = game.get_equity().unwrap();
+        assert!(equity[0].equity > 0.5 && equity[0].equity < 1.0);
+        assert!((equity[0].equity + equity[1].equity - 1.0).abs() < 1e-9);
+
+        // Folded queens leave the queens drawing thinner
+        let dead = StudGame::build(
+            vec![hand("AhAd", "Kc7s8d2h"), hand("QhQd", "Ks7h8s3d")],
+            parse_cards("QsQc").unwrap(),
+        ).unwrap();
+        assert!(dead.get_equity().unwrap()[1].equity < equity[1].equity);
+    }
+
+    #[test]
+    fn test_estimate_stud_equity() {
+        let game = StudGame::build(vec![hand("AhAd", "Ac"), hand("2h7d", "9s"), hand("3c8h", "Js")], vec![]).unwrap();
+        let equity = game.estimate_equity(2000, 7).unwrap();
+        assert_eq!(equity, game.estimate_equity(2000, 7).unwrap());
+        assert!(equity[0].equity > 0.7);
+        let total: f64 = equity.iter().map(|equity| equity.equity).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        // Third street has billions of deals, so only an estimate is possible
+        assert!(game.get_equity().is_err());
+        assert!(game.estimate_equity(0, 7).is_err());
+    }
+
+    #[test]
+    fn test_razz_equity() {
+        // Seventh street: the wheel wins at Razz even though it loses at high
+        let players = vec![hand("Ah2d3c", "4s5h9d9c"), hand("KhKdKc", "Ks7h8s9h")];
+        let razz = StudGame::build_variant(StudVariant::Razz, players.clone(), vec![]).unwrap();
+        assert_eq!(razz.get_equity().unwrap()[0].win, 1.0);
+        let stud = StudGame::build(players, vec![]).unwrap();
+        assert_eq!(stud.get_equity().unwrap()[1].win, 1.0);
+
+        let game = StudGame::build_variant(StudVariant::Razz, vec![hand("Ah2d", "3c4s"), hand("KhKd", "QcJs")], vec![]).unwrap();
+        let equity = game.estimate_equity(500, 1).unwrap();
+        assert!(equity[0].equity > 0.9);
+
+        // Razz on third street is refused exactly, like Seven Card Stud, and estimated instead
+        let game = StudGame::build_variant(StudVariant::Razz, vec![hand("Ah2d", "3c"), hand("KhKd", "Qc")], vec![]).unwrap();
+        assert_eq!(game.get_equity(), Err("Too many deals for an exact calculation; use estimate_equity instead"));
+        assert!(game.estimate_equity(500, 1).unwrap()[0].equity > 0.8);
+    }
+}
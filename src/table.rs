@@ -0,0 +1,619 @@
+//! A No-Limit Hold'em table that plays complete hands: antes and blinds, dealing from a shuffled
+//! deck, betting rounds with minimum raise rules, and a showdown. Chips are whole numbers, and a
+//! table built with the same seed deals the same cards every time.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use crate::cards::{get_deck_without, Card, HandRank, Variant};
+use crate::history::{Action, ActionKind, HandRecord, Player, Round};
+use crate::pots::{get_payouts, OddChipRule};
+
+/// A move by the player to act. Bets and raises give the player's total for the round.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlayerAction {
+    Fold,
+    Check,
+    Call,
+    Bet(u64),
+    Raise(u64),
+}
+
+/// What the player to act may do. Bet and raise sizes are inclusive ranges of totals for the
+/// round, where the top is all-in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegalActions {
+    pub fold: bool,
+    pub check: bool,
+    /// Chips needed to call, which is all of a short stack
+    pub call: Option<u64>,
+    pub bet: Option<(u64, u64)>,
+    pub raise: Option<(u64, u64)>,
+}
+
+impl LegalActions {
+    pub fn is_legal(&self, action: PlayerAction) -> bool {
+        let in_range = |range: Option<(u64, u64)>, to| range.is_some_and(|(min, max)| (min..=max).contains(&to));
+        match action {
+            PlayerAction::Fold => self.fold,
+            PlayerAction::Check => self.check,
+            PlayerAction::Call => self.call.is_some(),
+            PlayerAction::Bet(to) => in_range(self.bet, to),
+            PlayerAction::Raise(to) => in_range(self.raise, to),
+        }
+    }
+}
+
+/// A player's place at the table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Seat {
+    pub name: String,
+    pub stack: u64,
+    /// Empty for seats not dealt into the current hand
+    pub hole: Vec<Card>,
+    /// Chips put in during the current round
+    pub committed: u64,
+    /// Chips put in during the whole hand, including antes
+    pub invested: u64,
+    pub folded: bool,
+    /// Whether the seat was dealt into the current hand, which needs chips
+    pub in_hand: bool,
+    /// The bet the seat last acted at in the current round
+    acted_at: Option<u64>,
+    starting_stack: u64,
+}
+
+impl Seat {
+    /// Whether the seat is still contesting the pot
+    pub fn is_active(&self) -> bool {
+        self.in_hand && !self.folded
+    }
+
+    pub fn is_all_in(&self) -> bool {
+        self.is_active() && self.stack == 0
+    }
+
+    fn can_act(&self) -> bool {
+        self.is_active() && self.stack > 0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Table {
+    seats: Vec<Seat>,
+    small_blind: u64,
+    big_blind: u64,
+    ante: u64,
+    rng: StdRng,
+    deck: Vec<Card>,
+    button: Option<usize>,
+    board: Vec<Card>,
+    round: Round,
+    to_act: Option<usize>,
+    current_bet: u64,
+    /// The smallest increment a full raise can make
+    min_raise: u64,
+    /// The bet reached by the last full bet or raise, which reopens the betting
+    full_bet: u64,
+    hand_number: u64,
+    actions: Vec<Action>,
+    collected: Vec<u64>,
+    is_hand_over: bool,
+}
+
+impl Table {
+    /// Seats `players`, given as names and stacks, in order. The button starts with the first
+    /// player with chips.
+    pub fn build(players: &[(&str, u64)], small_blind: u64, big_blind: u64, ante: u64, seed: u64) -> Result<Table, &'static str> {
+        if players.len() < 2 || players.len() > 10 {
+            return Err("A table seats between two and ten players");
+        }
+        if players.iter().enumerate().any(|(index, (name, _))| players[..index].iter().any(|(other, _)| other == name)) {
+            return Err("Every player needs a different name");
+        }
+        if big_blind == 0 || small_blind > big_blind {
+            return Err("The big blind must be positive and at least the small blind");
+        }
+        let seats = players.iter()
+            .map(|(name, stack)| Seat {
+                name: name.to_string(),
+                stack: *stack,
+                hole: Vec::new(),
+                committed: 0,
+                invested: 0,
+                folded: false,
+                in_hand: false,
+                acted_at: None,
+                starting_stack: *stack,
+            })
+            .collect();
+        Ok(Table {
+            seats,
+            small_blind,
+            big_blind,
+            ante,
+            rng: StdRng::seed_from_u64(seed),
+            deck: Vec::new(),
+            button: None,
+            board: Vec::new(),
+            round: Round::Preflop,
+            to_act: None,
+            current_bet: 0,
+            min_raise: big_blind,
+            full_bet: 0,
+            hand_number: 0,
+            actions: Vec::new(),
+            collected: vec![0; players.len()],
+            is_hand_over: true,
+        })
+    }
+
+    pub fn seats(&self) -> &[Seat] {
+        &self.seats
+    }
+
+    pub fn board(&self) -> &[Card] {
+        &self.board
+    }
+
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
+    pub fn button(&self) -> Option<usize> {
+        self.button
+    }
+
+    /// The seat whose turn it is, if the hand is still being bet
+    pub fn to_act(&self) -> Option<usize> {
+        self.to_act
+    }
+
+    pub fn big_blind(&self) -> u64 {
+        self.big_blind
+    }
+
+    /// The total each player in the round must put in to stay in the hand
+    pub fn current_bet(&self) -> u64 {
+        self.current_bet
+    }
+
+    pub fn pot(&self) -> u64 {
+        self.seats.iter().map(|seat| seat.invested).sum()
+    }
+
+    pub fn hand_number(&self) -> u64 {
+        self.hand_number
+    }
+
+    pub fn is_hand_over(&self) -> bool {
+        self.is_hand_over
+    }
+
+    /// Everything that has happened in the current hand
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    /// Chips each seat won in the last hand to finish
+    pub fn collected(&self) -> &[u64] {
+        &self.collected
+    }
+
+    /// Sets a seat's stack between hands, such as for a rebuy
+    pub fn set_stack(&mut self, index: usize, stack: u64) -> Result<(), &'static str> {
+        if !self.is_hand_over {
+            return Err("Stacks can only change between hands");
+        }
+        self.seats.get_mut(index).ok_or("No such seat")?.stack = stack;
+        Ok(())
+    }
+
+    /// The next seat after `index` dealt into the hand
+    fn next_in_hand(&self, index: usize) -> usize {
+        (1..=self.seats.len())
+            .map(|offset| (index + offset) % self.seats.len())
+            .find(|&next| self.seats[next].in_hand)
+            .unwrap_or(index)
+    }
+
+    /// Seats dealt into the hand, starting left of the button
+    fn in_hand_from_button(&self) -> Vec<usize> {
+        let button = self.button.unwrap_or(0);
+        (1..=self.seats.len())
+            .map(|offset| (button + offset) % self.seats.len())
+            .filter(|&index| self.seats[index].in_hand)
+            .collect()
+    }
+
+    fn others_can_act(&self, index: usize) -> bool {
+        self.seats.iter().enumerate().any(|(other, seat)| other != index && seat.can_act())
+    }
+
+    fn needs_to_act(&self, index: usize) -> bool {
+        let seat = &self.seats[index];
+        seat.can_act()
+            && (seat.committed < self.current_bet || (seat.acted_at.is_none() && self.others_can_act(index)))
+    }
+
+    /// The first seat after `index` that still has to act in this round
+    fn next_to_act(&self, index: usize) -> Option<usize> {
+        (1..=self.seats.len())
+            .map(|offset| (index + offset) % self.seats.len())
+            .find(|&next| self.needs_to_act(next))
+    }
+
+    fn put_in(&mut self, index: usize, amount: u64) -> u64 {
+        let seat = &mut self.seats[index];
+        let amount = amount.min(seat.stack);
+        seat.stack -= amount;
+        seat.committed += amount;
+        seat.invested += amount;
+        amount
+    }
+
+    fn log(&mut self, index: usize, kind: ActionKind) {
+        let seat = &self.seats[index];
+        let is_all_in = seat.stack == 0 && !matches!(kind, ActionKind::Fold | ActionKind::Show(_));
+        self.actions.push(Action { player: seat.name.clone(), round: self.round, kind, is_all_in });
+    }
+
+    fn deal(&mut self) -> Card {
+        self.deck.pop().expect("A Hold'em hand never runs out of cards")
+    }
+
+    /// Moves the button, posts antes and blinds, and deals. Seats without chips sit the hand out.
+    pub fn start_hand(&mut self) -> Result<(), &'static str> {
+        if !self.is_hand_over {
+            return Err("The current hand is not over");
+        }
+        if self.seats.iter().filter(|seat| seat.stack > 0).count() < 2 {
+            return Err("At least two players need chips to play a hand");
+        }
+        for seat in &mut self.seats {
+            seat.in_hand = seat.stack > 0;
+            seat.hole.clear();
+            seat.committed = 0;
+            seat.invested = 0;
+            seat.folded = false;
+            seat.acted_at = None;
+            seat.starting_stack = seat.stack;
+        }
+        let button = match self.button {
+            Some(button) => self.next_in_hand(button),
+            None => self.next_in_hand(self.seats.len() - 1),
+        };
+        self.button = Some(button);
+        self.hand_number += 1;
+        self.actions.clear();
+        self.board.clear();
+        self.collected = vec![0; self.seats.len()];
+        self.round = Round::Preflop;
+        self.is_hand_over = false;
+        self.deck = get_deck_without(&[]);
+        self.deck.shuffle(&mut self.rng);
+
+        let order = self.in_hand_from_button();
+        if self.ante > 0 {
+            for &index in &order {
+                let amount = self.put_in(index, self.ante);
+                // Antes are dead money rather than part of the first round's bets
+                self.seats[index].committed -= amount;
+                self.log(index, ActionKind::PostAnte(amount as f64));
+            }
+        }
+        // Heads-up the button posts the small blind
+        let (small, big) = if order.len() == 2 { (button, order[0]) } else { (order[0], order[1]) };
+        let amount = self.put_in(small, self.small_blind);
+        self.log(small, ActionKind::PostSmallBlind(amount as f64));
+        let amount = self.put_in(big, self.big_blind);
+        self.log(big, ActionKind::PostBigBlind(amount as f64));
+        for _ in 0..2 {
+            for &index in &order {
+                let card = self.deal();
+                self.seats[index].hole.push(card);
+            }
+        }
+        self.current_bet = self.big_blind;
+        self.full_bet = self.big_blind;
+        self.min_raise = self.big_blind;
+        self.advance(big);
+        Ok(())
+    }
+
+    /// What the player to act may do, if anyone is to act
+    pub fn get_legal_actions(&self) -> Option<LegalActions> {
+        let index = self.to_act?;
+        let seat = &self.seats[index];
+        let to_call = self.current_bet.saturating_sub(seat.committed);
+        let all_in = seat.committed + seat.stack;
+        // A raise short of a full raise does not reopen the betting to those who already acted
+        let can_raise = seat.acted_at.is_none_or(|bet| bet < self.full_bet)
+            && self.others_can_act(index)
+            && all_in > self.current_bet;
+        let sizes = can_raise.then_some(((self.current_bet + self.min_raise).min(all_in), all_in));
+        Some(LegalActions {
+            fold: to_call > 0,
+            check: to_call == 0,
+            call: (to_call > 0).then_some(to_call.min(seat.stack)),
+            bet: sizes.filter(|_| self.current_bet == 0),
+            raise: sizes.filter(|_| self.current_bet > 0),
+        })
+    }
+
+    /// Plays `action` for the player to act, then moves the hand on
+    pub fn act(&mut self, action: PlayerAction) -> Result<(), &'static str> {
+        let legal = self.get_legal_actions().ok_or("No player is to act")?;
+        if !legal.is_legal(action) {
+            return Err("That action is not legal");
+        }
+        let index = self.to_act.expect("Legal actions need a player to act");
+        match action {
+            PlayerAction::Fold => {
+                self.seats[index].folded = true;
+                self.log(index, ActionKind::Fold);
+            }
+            PlayerAction::Check => self.log(index, ActionKind::Check),
+            PlayerAction::Call => {
+                let amount = self.put_in(index, self.current_bet - self.seats[index].committed);
+                self.log(index, ActionKind::Call(amount as f64));
+            }
+            PlayerAction::Bet(to) | PlayerAction::Raise(to) => {
+                let amount = self.put_in(index, to - self.seats[index].committed);
+                let increment = to - self.current_bet;
+                if increment >= self.min_raise {
+                    self.min_raise = increment;
+                    self.full_bet = to;
+                }
+                let kind = match action {
+                    PlayerAction::Bet(_) => ActionKind::Bet(amount as f64),
+                    _ => ActionKind::Raise(increment as f64, to as f64),
+                };
+                self.current_bet = to;
+                self.log(index, kind);
+            }
+        }
+        self.seats[index].acted_at = Some(self.current_bet);
+        self.advance(index);
+        Ok(())
+    }
+
+    /// Passes the action on after `index`, ending the round or the hand when betting is done
+    fn advance(&mut self, index: usize) {
+        if self.seats.iter().filter(|seat| seat.is_active()).count() == 1 {
+            self.return_uncalled_bet();
+            let winner = self.seats.iter().position(|seat| seat.is_active()).expect("One player is left");
+            let pot = self.pot();
+            self.collect(winner, pot);
+            self.finish_hand();
+            return;
+        }
+        self.to_act = self.next_to_act(index);
+        if self.to_act.is_none() {
+            self.return_uncalled_bet();
+            self.end_round();
+        }
+    }
+
+    /// Hands back the part of the largest bet that nobody matched
+    fn return_uncalled_bet(&mut self) {
+        let mut invested: Vec<(u64, usize)> = self.seats.iter().enumerate().map(|(index, seat)| (seat.invested, index)).collect();
+        invested.sort_unstable_by(|a, b| b.cmp(a));
+        let (top, index) = invested[0];
+        let uncalled = top - invested[1].0;
+        if uncalled > 0 {
+            let seat = &mut self.seats[index];
+            seat.stack += uncalled;
+            seat.invested -= uncalled;
+            seat.committed = seat.committed.saturating_sub(uncalled);
+            self.log(index, ActionKind::UncalledBetReturned(uncalled as f64));
+        }
+    }
+
+    /// Deals the next street, running the board out to a showdown when at most one player can
+    /// still bet
+    fn end_round(&mut self) {
+        for seat in &mut self.seats {
+            seat.committed = 0;
+            seat.acted_at = None;
+        }
+        self.current_bet = 0;
+        self.full_bet = 0;
+        self.min_raise = self.big_blind;
+        let is_run_out = self.seats.iter().filter(|seat| seat.can_act()).count() <= 1;
+        loop {
+            let (next, cards) = match self.round {
+                Round::Preflop => (Round::Flop, 3),
+                Round::Flop => (Round::Turn, 1),
+                Round::Turn => (Round::River, 1),
+                _ => {
+                    self.round = Round::Showdown;
+                    self.showdown();
+                    return;
+                }
+            };
+            self.deal();
+            for _ in 0..cards {
+                let card = self.deal();
+                self.board.push(card);
+            }
+            self.round = next;
+            if !is_run_out {
+                self.to_act = self.next_to_act(self.button.unwrap_or(0));
+                if self.to_act.is_some() {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn showdown(&mut self) {
+        let order: Vec<usize> = self.in_hand_from_button().into_iter().filter(|&index| self.seats[index].is_active()).collect();
+        for &index in &order {
+            self.log(index, ActionKind::Show(self.seats[index].hole.clone()));
+        }
+        let ranks: Vec<Option<HandRank>> = self.seats.iter()
+            .map(|seat| seat.is_active().then(|| Variant::Holdem.get_hand_rank(&seat.hole, &self.board)))
+            .collect();
+        let contributions: Vec<u64> = self.seats.iter().map(|seat| seat.invested).collect();
+        let payouts = get_payouts(&contributions, &ranks, &OddChipRule::LeftOfButton(self.button.unwrap_or(0)));
+        for (index, payout) in payouts.into_iter().enumerate() {
+            self.collect(index, payout);
+        }
+        self.finish_hand();
+    }
+
+    fn collect(&mut self, index: usize, amount: u64) {
+        self.seats[index].stack += amount;
+        self.collected[index] += amount;
+    }
+
+    fn finish_hand(&mut self) {
+        self.to_act = None;
+        self.is_hand_over = true;
+    }
+
+    /// The current hand as a hand record with every player's hole cards, so simulated hands can
+    /// be analysed or written out like real ones
+    pub fn to_hand_record(&self) -> HandRecord {
+        let players = self.seats.iter().enumerate()
+            .filter(|(_, seat)| seat.in_hand)
+            .map(|(index, seat)| Player {
+                seat: index + 1,
+                name: seat.name.clone(),
+                stack: seat.starting_stack as f64,
+                hole: Some(seat.hole.clone()),
+            })
+            .collect();
+        HandRecord {
+            id: self.hand_number.to_string(),
+            description: format!("Hold'em No Limit ({}/{})", self.small_blind, self.big_blind),
+            table: "Simulation".to_string(),
+            button: self.button.map_or(0, |button| button + 1),
+            players,
+            flop: self.board.get(..3).map(|flop| [flop[0], flop[1], flop[2]]),
+            turn: self.board.get(3).copied(),
+            river: self.board.get(4).copied(),
+            actions: self.actions.clone(),
+            pot: self.pot() as f64,
+            rake: 0.0,
+            winners: self.seats.iter().zip(&self.collected)
+                .filter(|(_, &amount)| amount > 0)
+                .map(|(seat, &amount)| (seat.name.clone(), amount as f64))
+                .collect(),
+            start_date_utc: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ohh::write_ohh;
+
+    fn total_chips(table: &Table) -> u64 {
+        table.seats().iter().map(|seat| seat.stack).sum::<u64>() + if table.is_hand_over() { 0 } else { table.pot() }
+    }
+
+    #[test]
+    fn test_betting_round() {
+        let mut table = Table::build(&[("A", 1000), ("B", 1000), ("C", 1000)], 5, 10, 0, 1).unwrap();
+        assert!(table.act(PlayerAction::Check).is_err());
+        table.start_hand().unwrap();
+        assert_eq!(table.button(), Some(0));
+        assert_eq!((table.seats()[1].committed, table.seats()[2].committed), (5, 10));
+        assert_eq!(table.to_act(), Some(0));
+        assert_eq!(table.get_legal_actions().unwrap(), LegalActions { fold: true, check: false, call: Some(10), bet: None, raise: Some((20, 1000)) });
+        assert!(table.act(PlayerAction::Raise(15)).is_err());
+        table.act(PlayerAction::Raise(30)).unwrap();
+        // The next raise must be at least as big as the last one
+        assert_eq!(table.get_legal_actions().unwrap().raise, Some((50, 1000)));
+        table.act(PlayerAction::Call).unwrap();
+        table.act(PlayerAction::Call).unwrap();
+        assert_eq!(table.round(), Round::Flop);
+        assert_eq!(table.board().len(), 3);
+        assert_eq!(table.pot(), 90);
+        assert_eq!(table.to_act(), Some(1));
+        assert_eq!(table.get_legal_actions().unwrap().bet, Some((10, 970)));
+        while let Some(legal) = table.get_legal_actions() {
+            table.act(if legal.check { PlayerAction::Check } else { PlayerAction::Call }).unwrap();
+        }
+        assert!(table.is_hand_over());
+        assert_eq!(table.board().len(), 5);
+        assert_eq!(table.collected().iter().sum::<u64>(), 90);
+        assert_eq!(total_chips(&table), 3000);
+
+        table.start_hand().unwrap();
+        assert_eq!(table.button(), Some(1));
+        assert_eq!(table.to_act(), Some(1));
+    }
+
+    #[test]
+    fn test_short_all_in_does_not_reopen_betting() {
+        let mut table = Table::build(&[("A", 1000), ("B", 1000), ("C", 35)], 5, 10, 0, 2).unwrap();
+        table.start_hand().unwrap();
+        table.act(PlayerAction::Raise(30)).unwrap();
+        table.act(PlayerAction::Call).unwrap();
+        let legal = table.get_legal_actions().unwrap();
+        assert_eq!(legal.raise, Some((35, 35)));
+        table.act(PlayerAction::Raise(35)).unwrap();
+        assert!(table.seats()[2].is_all_in());
+        // A raise of five is short of the last full raise of twenty
+        let legal = table.get_legal_actions().unwrap();
+        assert_eq!((legal.call, legal.raise), (Some(5), None));
+        table.act(PlayerAction::Call).unwrap();
+        table.act(PlayerAction::Call).unwrap();
+        assert_eq!(table.round(), Round::Flop);
+        table.act(PlayerAction::Bet(100)).unwrap();
+        table.act(PlayerAction::Fold).unwrap();
+        // Everyone else is all-in or folded, so the board runs out
+        assert!(table.is_hand_over());
+        assert_eq!(table.board().len(), 5);
+        assert!(table.actions().contains(&Action { player: "B".to_string(), round: Round::Flop, kind: ActionKind::UncalledBetReturned(100.0), is_all_in: false }));
+        assert_eq!(table.collected().iter().sum::<u64>(), 105);
+        assert_eq!(total_chips(&table), 2035);
+    }
+
+    #[test]
+    fn test_heads_up_and_record() {
+        let mut table = Table::build(&[("A", 200), ("B", 200)], 1, 2, 1, 3).unwrap();
+        table.start_hand().unwrap();
+        // Heads-up the button is the small blind and acts first before the flop
+        assert_eq!(table.button(), Some(0));
+        assert_eq!(table.to_act(), Some(0));
+        table.act(PlayerAction::Raise(6)).unwrap();
+        table.act(PlayerAction::Fold).unwrap();
+        assert!(table.is_hand_over());
+        assert_eq!(table.collected(), &[6, 0]);
+        assert_eq!((table.seats()[0].stack, table.seats()[1].stack), (203, 197));
+
+        let record = table.to_hand_record();
+        assert_eq!(record.actions.last().unwrap().kind, ActionKind::UncalledBetReturned(4.0));
+        assert_eq!(record.winners, vec![("A".to_string(), 6.0)]);
+        assert!(write_ohh(&record).is_ok());
+
+        table.set_stack(1, 0).unwrap();
+        assert!(table.start_hand().is_err());
+    }
+
+    #[test]
+    fn test_seed_is_deterministic() {
+        let play = |seed| {
+            let mut table = Table::build(&[("A", 50), ("B", 50), ("C", 50)], 1, 2, 0, seed).unwrap();
+            table.start_hand().unwrap();
+            while let Some(legal) = table.get_legal_actions() {
+                let action = match legal.raise {
+                    Some((_, all_in)) => PlayerAction::Raise(all_in),
+                    None if legal.check => PlayerAction::Check,
+                    None => PlayerAction::Call,
+                };
+                table.act(action).unwrap();
+            }
+            (table.to_hand_record(), total_chips(&table))
+        };
+        let (first, chips) = play(7);
+        assert_eq!(chips, 150);
+        assert_eq!(first, play(7).0);
+        assert_ne!(first.players, play(8).0.players);
+    }
+}
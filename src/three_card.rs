@@ -0,0 +1,249 @@
+//! Three card hands, for Three Card Poker and the top row in Open-face Chinese Poker
+
+use itertools::Itertools;
+use crate::cards::{get_deck_without, Card, HandRank};
+
+/// Ranks three cards where only pairs and three of a kind count, as in the top row in
+/// Open-face Chinese Poker. The rank compares directly with five card ranks from `get_hand_rank`,
+/// with a three card hand below any five card hand of the same pair and higher kickers.
+pub fn get_three_card_hand_rank(cards: &[Card]) -> HandRank {
+    let mut values: Vec<u8> = cards.iter().map(|card| card.value()).collect();
+    values.sort_by_key(|value| std::cmp::Reverse(*value));
+    if values[0] == values[2] {
+        HandRank::new(3, &values[..1])
+    } else if values[0] == values[1] {
+        HandRank::new(1, &[values[0], values[2]])
+    } else if values[1] == values[2] {
+        HandRank::new(1, &[values[1], values[0]])
+    } else {
+        HandRank::new(0, &values)
+    }
+}
+
+/// Ranks three cards for Three Card Poker, where a straight beats a flush and three of a kind
+/// beats a straight. A-2-3 is the lowest straight and A-K-Q the highest.
+pub fn get_three_card_poker_rank(cards: &[Card]) -> HandRank {
+    let mut values: Vec<u8> = cards.iter().map(|card| card.value()).collect();
+    values.sort_by_key(|value| std::cmp::Reverse(*value));
+    let is_flush = cards.iter().all(|card| card.suit() == cards[0].suit());
+    let straight_high = if values == [14, 3, 2] {
+        Some(3)
+    } else if values[0] == values[1] + 1 && values[1] == values[2] + 1 {
+        Some(values[0])
+    } else {
+        None
+    };
+    match (straight_high, is_flush) {
+        (Some(high), true) => HandRank::new(8, &[high]).with_order(5),
+        _ if values[0] == values[2] => HandRank::new(3, &values[..1]).with_order(4),
+        (Some(high), false) => HandRank::new(4, &[high]).with_order(3),
+        (None, true) => HandRank::new(5, &values).with_order(2),
+        _ => get_three_card_hand_rank(cards),
+    }
+}
+
+/// Three Card Poker hands from worst to best, as ordered by `get_three_card_poker_rank`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ThreeCardHand {
+    HighCard,
+    Pair,
+    Flush,
+    Straight,
+    ThreeOfAKind,
+    StraightFlush,
+}
+
+impl ThreeCardHand {
+    pub fn from_rank(rank: HandRank) -> ThreeCardHand {
+        match rank.order() {
+            0 => ThreeCardHand::HighCard,
+            1 => ThreeCardHand::Pair,
+            2 => ThreeCardHand::Flush,
+            3 => ThreeCardHand::Straight,
+            4 => ThreeCardHand::ThreeOfAKind,
+            _ => ThreeCardHand::StraightFlush,
+        }
+    }
+}
+
+/// Units won per unit bet on each Three Card Poker bonus
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ThreeCardPaytable {
+    /// Pair Plus pays on a pair, flush, straight, three of a kind, and straight flush, and loses
+    /// otherwise
+    pub pair_plus: [f64; 5],
+    /// The ante bonus pays on a straight, three of a kind, and straight flush whenever the hand
+    /// is played, whatever the dealer holds
+    pub ante_bonus: [f64; 3],
+}
+
+/// The common 1-4-6-30-40 Pair Plus table with a 1-4-5 ante bonus
+impl Default for ThreeCardPaytable {
+    fn default() -> ThreeCardPaytable {
+        ThreeCardPaytable { pair_plus: [1.0, 4.0, 6.0, 30.0, 40.0], ante_bonus: [1.0, 4.0, 5.0] }
+    }
+}
+
+/// Expected result of each choice with a Three Card Poker hand, in units of the ante
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AnteReturn {
+    /// Folding always loses the ante
+    pub fold: f64,
+    /// Placing a play bet equal to the ante, including any ante bonus
+    pub play: f64,
+}
+
+impl AnteReturn {
+    pub fn should_play(&self) -> bool {
+        self.play > self.fold
+    }
+}
+
+fn check_hand(cards: &[Card]) -> Result<(), &'static str> {
+    if cards.len() != 3 {
+        return Err("A Three Card Poker hand has three cards");
+    }
+    if cards.iter().enumerate().any(|(index, card)| cards[..index].contains(card) || card.is_joker()) {
+        return Err("A hand must be three different cards from a standard deck");
+    }
+    Ok(())
+}
+
+impl ThreeCardPaytable {
+    fn pair_plus_payout(&self, cards: &[Card]) -> f64 {
+        match ThreeCardHand::from_rank(get_three_card_poker_rank(cards)) {
+            ThreeCardHand::HighCard => -1.0,
+            hand => self.pair_plus[hand as usize - 1],
+        }
+    }
+
+    fn ante_bonus_payout(&self, cards: &[Card]) -> f64 {
+        match ThreeCardHand::from_rank(get_three_card_poker_rank(cards)) {
+            hand if hand >= ThreeCardHand::Straight => self.ante_bonus[hand as usize - 3],
+            _ => 0.0,
+        }
+    }
+
+    /// Units won on a Pair Plus bet, or -1 when it loses
+    pub fn get_pair_plus_payout(&self, cards: &[Card]) -> Result<f64, &'static str> {
+        check_hand(cards)?;
+        Ok(self.pair_plus_payout(cards))
+    }
+
+    /// Units of ante bonus won by a played hand
+    pub fn get_ante_bonus_payout(&self, cards: &[Card]) -> Result<f64, &'static str> {
+        check_hand(cards)?;
+        Ok(self.ante_bonus_payout(cards))
+    }
+
+    /// Expected return per unit of a Pair Plus bet over every hand
+    pub fn get_pair_plus_return(&self) -> f64 {
+        let hands = get_deck_without(&[]).into_iter().combinations(3);
+        let (total, count) = hands.fold((0.0, 0), |(total, count), hand| (total + self.pair_plus_payout(&hand), count + 1));
+        total / count as f64
+    }
+
+    /// Expected ante bonus per unit of ante over every hand, every one of which is played when it
+    /// qualifies for the bonus
+    pub fn get_ante_bonus_return(&self) -> f64 {
+        let hands = get_deck_without(&[]).into_iter().combinations(3);
+        let (total, count) = hands.fold((0.0, 0), |(total, count), hand| (total + self.ante_bonus_payout(&hand), count + 1));
+        total / count as f64
+    }
+
+    /// Expected return of folding and of playing `cards` against every dealer hand. The dealer
+    /// needs queen high or better to qualify; if not, the ante wins even money and the play bet
+    /// pushes. Otherwise both bets win or lose even money against the dealer's hand.
+    pub fn get_ante_return(&self, cards: &[Card]) -> Result<AnteReturn, &'static str> {
+        check_hand(cards)?;
+        let rank = get_three_card_poker_rank(cards);
+        let qualifier = HandRank::new(0, &[12]);
+        let (mut total, mut count) = (0.0, 0);
+        for dealer in get_deck_without(cards).into_iter().combinations(3) {
+            let dealer = get_three_card_poker_rank(&dealer);
+            total += if dealer < qualifier {
+                1.0
+            } else {
+                2.0 * rank.cmp(&dealer) as i32 as f64
+            };
+            count += 1;
+        }
+        Ok(AnteReturn { fold: -1.0, play: total / count as f64 + self.ante_bonus_payout(cards) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{get_hand_rank, parse_cards, Hand};
+
+    fn poker_rank(cards: &str) -> HandRank {
+        get_three_card_poker_rank(&parse_cards(cards).unwrap())
+    }
+
+    fn rank(cards: &str) -> HandRank {
+        get_three_card_hand_rank(&parse_cards(cards).unwrap())
+    }
+
+    #[test]
+    fn test_get_three_card_hand_rank() {
+        assert_eq!(rank("AhKhQh").hand(), Hand::HighCard(14));
+        assert_eq!(rank("5h5dAh").values(), vec![5, 14]);
+        assert_eq!(rank("Ah5d5h").hand(), Hand::Pair(5));
+        assert_eq!(rank("2h2d2c").hand(), Hand::ThreeOfAKind(2));
+        assert!(rank("2h2d2c") > rank("AhAdKc"));
+        assert!(rank("AhAdKc") > rank("AhAdQc"));
+        // Compared with five cards, the same values rank the same until the three cards run out
+        assert!(rank("QhQd5c") < get_hand_rank(&parse_cards("QsQc5d4h3s").unwrap()));
+        assert!(rank("QhQd5c") > get_hand_rank(&parse_cards("JsJcAdKhQs").unwrap()));
+        assert!(rank("2h2d2c") < get_hand_rank(&parse_cards("As2c5d4h3s").unwrap()));
+    }
+
+    #[test]
+    fn test_get_three_card_poker_rank() {
+        let hand = |cards: &str| ThreeCardHand::from_rank(poker_rank(cards));
+        assert_eq!(hand("AhKdQc"), ThreeCardHand::Straight);
+        assert_eq!(hand("Ah2d3c"), ThreeCardHand::Straight);
+        assert_eq!(hand("Ah2h4h"), ThreeCardHand::Flush);
+        assert_eq!(hand("5h5d5c"), ThreeCardHand::ThreeOfAKind);
+        assert_eq!(hand("9h8h7h"), ThreeCardHand::StraightFlush);
+        assert_eq!(hand("9h9d7h"), ThreeCardHand::Pair);
+        assert_eq!(hand("KhJd7h"), ThreeCardHand::HighCard);
+        // Unlike five card poker, a straight beats a flush and three of a kind beats a straight
+        assert!(poker_rank("4h3d2c") > poker_rank("AhKhJh"));
+        assert!(poker_rank("2h2d2c") > poker_rank("AhKdQc"));
+        assert!(poker_rank("Ah2d3c") < poker_rank("4h3d2c"));
+        assert!(poker_rank("3h2h4h") > poker_rank("AhAdAc"));
+        assert!(poker_rank("KhKd9c") > poker_rank("KsKc8d"));
+    }
+
+    #[test]
+    fn test_three_card_paytable() {
+        let paytable = ThreeCardPaytable::default();
+        assert_eq!(paytable.get_pair_plus_payout(&parse_cards("KhKd9c").unwrap()), Ok(1.0));
+        assert_eq!(paytable.get_pair_plus_payout(&parse_cards("KhJd9c").unwrap()), Ok(-1.0));
+        assert_eq!(paytable.get_ante_bonus_payout(&parse_cards("KhJh9h").unwrap()), Ok(0.0));
+        assert_eq!(paytable.get_ante_bonus_payout(&parse_cards("5h5d5c").unwrap()), Ok(4.0));
+        // Short hands, repeated cards and jokers are errors rather than panics or made-up payouts
+        assert!(paytable.get_pair_plus_payout(&parse_cards("KhKd").unwrap()).is_err());
+        assert!(paytable.get_pair_plus_payout(&parse_cards("KhKhKh").unwrap()).is_err());
+        assert!(paytable.get_ante_bonus_payout(&[]).is_err());
+        assert!(paytable.get_ante_bonus_payout(&[Card::joker(), parse_cards("Kh").unwrap()[0], parse_cards("Kd").unwrap()[0]]).is_err());
+        // The 1-4-6-30-40 table has a house edge of about 2.32%
+        assert!((paytable.get_pair_plus_return() + 0.0232).abs() < 0.0001);
+        // Straights, trips, and straight flushes make up 820 of the 22100 hands
+        let expected = (720.0 + 4.0 * 52.0 + 5.0 * 48.0) / 22100.0;
+        assert!((paytable.get_ante_bonus_return() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_get_ante_return() {
+        let paytable = ThreeCardPaytable::default();
+        // Queen-six-four is the weakest hand worth playing
+        assert!(paytable.get_ante_return(&parse_cards("Qh6d4c").unwrap()).unwrap().should_play());
+        assert!(!paytable.get_ante_return(&parse_cards("Qh6d3c").unwrap()).unwrap().should_play());
+        let trips = paytable.get_ante_return(&parse_cards("AhAdAc").unwrap()).unwrap();
+        assert!(trips.play > 4.0);
+        assert!(paytable.get_ante_return(&parse_cards("AhAd").unwrap()).is_err());
+    }
+}
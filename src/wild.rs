@@ -0,0 +1,188 @@
+//! Wild cards, such as a joker or deuces wild. A wild card stands for whichever card makes the
+//! best hand, so five of a kind becomes possible and ranks above a royal flush.
+
+use crate::cards::{get_deck_without, get_equity_with, get_hand_rank, Card, Equity, HandRank, SUITS};
+
+/// Which cards are wild
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WildCards {
+    /// A 53 card deck with one joker, which is wild
+    Jokers,
+    /// A standard deck where every two is wild
+    Deuces,
+}
+
+impl WildCards {
+    pub fn is_wild(&self, card: &Card) -> bool {
+        match self {
+            WildCards::Jokers => card.is_joker(),
+            WildCards::Deuces => card.is_joker() || card.value() == 2,
+        }
+    }
+
+    /// The cards these games are dealt from
+    pub fn deck(&self) -> Vec<Card> {
+        let mut deck = get_deck_without(&[]);
+        if *self == WildCards::Jokers {
+            deck.push(Card::joker());
+        }
+        deck
+    }
+}
+
+/// Whether a straight topped by `high` can be completed from the values in `mask` with `wilds`
+/// wild cards. An ace also plays low, below the two.
+fn can_make_straight(mask: u16, high: u8, wilds: usize) -> bool {
+    let missing = (high - 4..=high)
+        .map(|value| if value == 1 { 14 } else { value })
+        .filter(|value| mask & (1 << value) == 0)
+        .count();
+    missing <= wilds
+}
+
+fn get_best_straight(mask: u16, wilds: usize) -> Option<u8> {
+    (5..=14u8).rev().find(|high| can_make_straight(mask, *high, wilds))
+}
+
+/// Ranks the best five-card hand among five or more cards, with each wild card assigned
+/// whichever value and suit makes the hand best. A wild card may duplicate a card already in
+/// the hand, except within a flush.
+pub fn get_wild_hand_rank(cards: &[Card], wild_cards: WildCards) -> HandRank {
+    let wilds = cards.iter().filter(|card| wild_cards.is_wild(card)).count();
+    if wilds == 0 {
+        return get_hand_rank(cards);
+    }
+    let mut counts = [0usize; 15];
+    let mut suit_masks = [0u16; 4];
+    for card in cards.iter().filter(|card| !wild_cards.is_wild(card)) {
+        counts[card.value() as usize] += 1;
+        let suit = SUITS.iter().position(|suit| *suit == card.suit()).unwrap();
+        suit_masks[suit] |= 1 << card.value();
+    }
+    let value_mask = suit_masks.iter().fold(0, |mask, suit_mask| mask | suit_mask);
+    let kickers = |used: &[u8], n: usize| -> Vec<u8> {
+        let mut values = used.to_vec();
+        values.extend((2..=14u8).rev().filter(|value| counts[*value as usize] > 0 && !used.contains(value)).take(n));
+        values
+    };
+    let with_wilds = |needed: usize| (2..=14u8).rev().find(|value| counts[*value as usize] + wilds >= needed);
+
+    if let Some(value) = with_wilds(5) {
+        return HandRank::new(9, &[value]);
+    }
+    if let Some(high) = suit_masks.iter().filter_map(|mask| get_best_straight(*mask, wilds)).max() {
+        return HandRank::new(8, &[high]);
+    }
+    if let Some(value) = with_wilds(4) {
+        return HandRank::new(7, &kickers(&[value], 1));
+    }
+    for trips in (2..=14u8).rev() {
+        for pair in (2..=14u8).rev().filter(|pair| *pair != trips) {
+            let needed = 3usize.saturating_sub(counts[trips as usize]) + 2usize.saturating_sub(counts[pair as usize]);
+            if needed <= wilds {
+                return HandRank::new(6, &[trips, pair]);
+            }
+        }
+    }
+    let flushes = suit_masks.iter().filter(|mask| mask.count_ones() as usize + wilds >= 5).map(|mask| {
+        // Wild cards fill in the highest values missing from the suit
+        let mut unused_wilds = wilds;
+        (2..=14u8).rev()
+            .filter(|value| {
+                if mask & (1 << value) != 0 {
+                    return true;
+                }
+                let is_filled = unused_wilds > 0;
+                unused_wilds = unused_wilds.saturating_sub(1);
+                is_filled
+            })
+            .take(5)
+            .collect::<Vec<u8>>()
+    });
+    if let Some(values) = flushes.max() {
+        return HandRank::new(5, &values);
+    }
+    if let Some(high) = get_best_straight(value_mask, wilds) {
+        return HandRank::new(4, &[high]);
+    }
+    // Any leftover wild card would have made a better hand above, so a single wild card pairs
+    // the highest card and two make three of a kind
+    if let Some(value) = with_wilds(3) {
+        return HandRank::new(3, &kickers(&[value], 2));
+    }
+    let value = with_wilds(2).unwrap();
+    HandRank::new(1, &kickers(&[value], 3))
+}
+
+/// Calculates the Hold'em equity of each hand with wild cards, by enumerating every runout of the
+/// remaining board cards from the wild card game's deck
+pub fn get_wild_equity(hands: &[Vec<Card>], board: &[Card], dead_cards: &[Card], wild_cards: WildCards) -> Result<Vec<Equity>, &'static str> {
+    get_equity_with(hands, board, dead_cards, &wild_cards.deck(), 5, |hand, board| {
+        let mut cards = hand.to_vec();
+        cards.extend(board);
+        get_wild_hand_rank(&cards, wild_cards)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{get_equity, parse_cards, Hand};
+
+    fn rank(cards: &str, wild_cards: WildCards) -> HandRank {
+        get_wild_hand_rank(&parse_cards(cards).unwrap(), wild_cards)
+    }
+
+    #[test]
+    fn test_joker() {
+        let joker: Card = "Jk".parse().unwrap();
+        assert!(joker.is_joker());
+        assert_eq!(joker.to_string(), "Jk");
+        assert_eq!(parse_cards("AhJkKd").unwrap()[1], Card::joker());
+        assert_eq!(WildCards::Jokers.deck().len(), 53);
+        assert_eq!(WildCards::Deuces.deck().len(), 52);
+    }
+
+    #[test]
+    fn test_get_wild_hand_rank() {
+        let jokers = |cards: &str| rank(cards, WildCards::Jokers).hand();
+        assert_eq!(jokers("AhAdAcAsJk"), Hand::FiveOfAKind(14));
+        assert_eq!(jokers("KhQhJhThJk"), Hand::RoyalFlush);
+        assert_eq!(jokers("9h8h6h5hJk2c"), Hand::StraightFlush(9));
+        assert_eq!(jokers("KhKdKc2s3dJk"), Hand::FourOfAKind(13));
+        assert_eq!(jokers("KhKdQcQs3dJk"), Hand::FullHouse(13, 12));
+        assert_eq!(jokers("Kh9h6h3h2cJk"), Hand::Flush(14));
+        assert_eq!(jokers("Ah2d3c5sJk9d"), Hand::Straight(5));
+        assert_eq!(jokers("KhKd9c5s2dJk"), Hand::ThreeOfAKind(13));
+        assert_eq!(jokers("Kh8d9c5s2dJk"), Hand::Pair(13));
+        // The ranker used everywhere else treats a joker as wild too
+        assert_eq!(get_hand_rank(&parse_cards("AhAdAcAsJk").unwrap()).hand(), Hand::FiveOfAKind(14));
+        assert!(rank("AhAdAcAsJk", WildCards::Jokers) > rank("AhKhQhJhTh", WildCards::Jokers));
+        // A wild flush card fills the highest value missing from the suit
+        assert_eq!(rank("Ah9h6h3h2cJk", WildCards::Jokers).values(), vec![14, 13, 9, 6, 3]);
+
+        let deuces = |cards: &str| rank(cards, WildCards::Deuces).hand();
+        assert_eq!(deuces("2h2d2c2sAh"), Hand::FiveOfAKind(14));
+        assert_eq!(deuces("2h2d7c8s9h"), Hand::Straight(11));
+        assert_eq!(deuces("2h2dKcQs3h"), Hand::ThreeOfAKind(13));
+        assert!(rank("2hAdAcAsAh", WildCards::Deuces) > rank("AhKhQhJhTh", WildCards::Deuces));
+        // Without wild cards the hand ranks as usual
+        assert_eq!(deuces("AhKdQc9s7h"), Hand::HighCard(14));
+    }
+
+    #[test]
+    fn test_get_wild_equity() {
+        let hands = [parse_cards("AhAd").unwrap(), parse_cards("KhKd").unwrap()];
+        // Only the last ace saves the aces' full house from four kings, and in a joker deck the
+        // joker river makes the kings five of a kind
+        let board = parse_cards("AcKcKs2d").unwrap();
+        let plain = get_equity(&hands, &board, &[]).unwrap();
+        assert!((plain[0].equity - 1.0 / 44.0).abs() < 1e-9);
+        let equity = get_wild_equity(&hands, &board, &[], WildCards::Jokers).unwrap();
+        assert!((equity[0].equity - 1.0 / 45.0).abs() < 1e-9);
+        // Deuces wild: the two on the board gives the aces four of a kind
+        let board = parse_cards("Ac7s4d2c").unwrap();
+        let equity = get_wild_equity(&hands, &board, &[], WildCards::Deuces).unwrap();
+        assert!(equity[0].equity > 0.9);
+    }
+}
@@ -0,0 +1,31 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Compiles the C harness against the generated header and the cdylib, then runs it
+#[test]
+fn c_harness() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // The cdylib is built next to the test executable
+    let lib_dir = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let harness = lib_dir.join("poker_odds_c_harness");
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(compiler)
+        .arg(manifest_dir.join("tests/ffi/harness.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg("-lpoker_odds")
+        .arg("-o")
+        .arg(&harness)
+        .status()
+        .expect("C compiler should be available");
+    assert!(status.success(), "C harness failed to compile");
+    let status = Command::new(&harness)
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .env("DYLD_LIBRARY_PATH", &lib_dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "C harness failed");
+}
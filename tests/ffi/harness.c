@@ -0,0 +1,68 @@
+#include <stdio.h>
+#include <string.h>
+#include "poker_odds.h"
+
+static int failures = 0;
+
+#define CHECK(condition)                                                   \
+    do {                                                                   \
+        if (!(condition)) {                                                \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,        \
+                    __LINE__, #condition);                                 \
+            failures++;                                                    \
+        }                                                                  \
+    } while (0)
+
+static void test_evaluate_hand(void) {
+    PoHandRank royal, quads, pair;
+    CHECK(po_evaluate_hand("AhKhQhJhTh", &royal) == PO_STATUS_OK);
+    CHECK(royal.category == PO_HAND_CATEGORY_ROYAL_FLUSH);
+    CHECK(po_evaluate_hand("9h9d9c9sKd2d", &quads) == PO_STATUS_OK);
+    CHECK(quads.category == PO_HAND_CATEGORY_FOUR_OF_A_KIND);
+    CHECK(po_evaluate_hand("7h7d3c4s9dQcKh", &pair) == PO_STATUS_OK);
+    CHECK(pair.category == PO_HAND_CATEGORY_PAIR);
+    CHECK(royal.rank > quads.rank && quads.rank > pair.rank);
+
+    CHECK(po_evaluate_hand("AhKh", &pair) == PO_STATUS_INVALID_GAME);
+    CHECK(po_evaluate_hand("AhKhQhJhXh", &pair) == PO_STATUS_INVALID_CARD);
+    CHECK(strlen(po_last_error()) > 0);
+    CHECK(po_evaluate_hand(NULL, &pair) == PO_STATUS_NULL_POINTER);
+}
+
+static void test_game_equity(void) {
+    PoGame *game = NULL;
+    PoRange *range = NULL;
+    PoEquity equity;
+    CHECK(po_game_new("AhAd", "2h7d9cJs4s", &game) == PO_STATUS_OK);
+    CHECK(po_range_parse("AA", &range) == PO_STATUS_OK);
+    CHECK(po_range_len(range) == 6);
+    CHECK(po_game_equity(game, range, &equity) == PO_STATUS_OK);
+    CHECK(equity.tie == 1.0 && equity.equity == 0.5);
+    po_range_free(range);
+
+    CHECK(po_range_parse("KK", &range) == PO_STATUS_OK);
+    CHECK(po_game_equity(game, range, &equity) == PO_STATUS_OK);
+    CHECK(equity.win == 1.0);
+    po_range_free(range);
+
+    CHECK(po_range_parse("AhAd", &range) == PO_STATUS_OK);
+    CHECK(po_game_equity(game, range, &equity) == PO_STATUS_INVALID_EQUITY);
+    po_range_free(range);
+    po_game_free(game);
+
+    game = NULL;
+    CHECK(po_game_new("AhAh", "2h7d9c", &game) == PO_STATUS_INVALID_GAME);
+    CHECK(game == NULL);
+    CHECK(po_range_parse("AKx", &range) == PO_STATUS_INVALID_RANGE);
+    CHECK(po_game_equity(NULL, NULL, &equity) == PO_STATUS_NULL_POINTER);
+}
+
+int main(void) {
+    test_evaluate_hand();
+    test_game_equity();
+    if (failures > 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return 1;
+    }
+    return 0;
+}